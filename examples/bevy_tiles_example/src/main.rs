@@ -21,21 +21,22 @@ fn startup(
     let dungeon = DungeonConfigBuilder::new()
         .dungeon_size(DungeonSize { width: 32, height: 32 })
         .room_size(RoomSize { min_room_size: 5, max_room_size: 5 })
-        .build_algorithm(BinaryPartitionBuilder)
+        .build_algorithm(BinaryPartitionBuilder::new())
         .should_place_doors(false)
+        .should_place_stairs(true)
         .build()
         .expect("Failed to build dungeon");
 
     // let dungeon = DungeonConfigBuilder::new()
     //     .dungeon_size(DungeonSize { width: 43, height: 50 })
     //     .room_size(RoomSize { min_room_size: 4, max_room_size: 6 })
-    //     .build_algorithm(RoomPlacementBuilder)
+    //     .build_algorithm(RoomPlacementBuilder::new())
     //     .should_place_doors(false)
     //     .build()
     //     .expect("Failed to build dungeon");
 
     let random_tile_map = dungeon.map;
-    let map_size = TilemapSize { x: random_tile_map.len() as u32, y: random_tile_map[0].len() as u32 };
+    let map_size = TilemapSize { x: random_tile_map.width() as u32, y: random_tile_map.height() as u32 };
     let tilemap_entity = commands.spawn_empty().id();
     let mut tile_storage = TileStorage::empty(map_size);
 
@@ -45,7 +46,7 @@ fn startup(
             let tile_entity = commands.spawn(TileBundle {
                 position: tile_pos,
                 tilemap_id: TilemapId(tilemap_entity),
-                texture_index: TileTextureIndex(random_tile_map[x as usize][y as usize] as u32),
+                texture_index: TileTextureIndex(random_tile_map[(x as usize, y as usize)] as u32),
                 ..Default::default()
             }).id();
 
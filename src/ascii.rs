@@ -0,0 +1,125 @@
+use std::fmt;
+use justerror::Error;
+use crate::dungeon::{Dungeon, TileType};
+use crate::grid::Grid;
+
+/// Characters used to render and parse every tile type
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GlyphTable {
+    pub wall: char,
+    pub floor: char,
+    pub door: char,
+    pub stairs_up: char,
+    pub stairs_down: char,
+    pub locked_door: char,
+}
+
+impl GlyphTable {
+    pub fn glyph(&self, tile: TileType) -> char {
+        match tile {
+            TileType::Wall => self.wall,
+            TileType::Floor => self.floor,
+            TileType::Door => self.door,
+            TileType::StairsUp => self.stairs_up,
+            TileType::StairsDown => self.stairs_down,
+            TileType::LockedDoor => self.locked_door,
+        }
+    }
+
+    pub fn tile(&self, glyph: char) -> Option<TileType> {
+        [
+            TileType::Wall,
+            TileType::Floor,
+            TileType::Door,
+            TileType::StairsUp,
+            TileType::StairsDown,
+            TileType::LockedDoor,
+        ]
+        .into_iter()
+        .find(|&tile| self.glyph(tile) == glyph)
+    }
+}
+
+impl Default for GlyphTable {
+    fn default() -> Self {
+        Self {
+            wall: '#',
+            floor: '.',
+            door: '+',
+            stairs_up: '<',
+            stairs_down: '>',
+            locked_door: '=',
+        }
+    }
+}
+
+#[Error(desc = "Ascii map parsing error", fmt = debug)]
+pub enum AsciiParseError {
+    #[error(desc = "Map has no rows", fmt = display)]
+    Empty,
+    #[error(desc = "Unknown glyph '{0}' at x = {1}, y = {2}", fmt = display)]
+    UnknownGlyph(char, usize, usize),
+    #[error(desc = "Row {0} has {2} tiles, but expected {1}", fmt = display)]
+    RaggedRow(usize, usize, usize),
+}
+
+impl Dungeon {
+    /// Renders one line per row, every line ends with a new line
+    pub fn to_ascii(&self, glyphs: &GlyphTable) -> String {
+        let mut ascii = String::with_capacity((self.map.width() + 1) * self.map.height());
+        for row in self.map.rows() {
+            ascii.extend(row.iter().map(|&tile| glyphs.glyph(tile)));
+            ascii.push('\n');
+        }
+        ascii
+    }
+
+    /// Parses map rendered with default glyphs
+    pub fn from_ascii(ascii: &str) -> Result<Dungeon, AsciiParseError> {
+        Self::from_ascii_with(ascii, &GlyphTable::default())
+    }
+
+    /// Parses map rendered with provided glyphs. Rooms and corridors are not restored,
+    /// entrance and exit are taken from the first stairs tiles
+    pub fn from_ascii_with(ascii: &str, glyphs: &GlyphTable) -> Result<Dungeon, AsciiParseError> {
+        let mut rows = Vec::new();
+
+        for (y, line) in ascii.lines().enumerate() {
+            let row = line
+                .chars()
+                .enumerate()
+                .map(|(x, glyph)| glyphs.tile(glyph).ok_or(AsciiParseError::UnknownGlyph(glyph, x, y)))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if let Some(first) = rows.first().map(Vec::len) {
+                if row.len() != first {
+                    return Err(AsciiParseError::RaggedRow(y, first, row.len()));
+                }
+            }
+
+            rows.push(row);
+        }
+
+        if rows.first().is_none_or(Vec::is_empty) {
+            return Err(AsciiParseError::Empty);
+        }
+
+        let map = Grid::from_nested(rows).ok_or(AsciiParseError::Empty)?;
+        let find = |tile| map.cells().find(|&(_, &t)| t == tile).map(|(position, _)| position);
+
+        Ok(Dungeon {
+            entrance: find(TileType::StairsUp),
+            exit: find(TileType::StairsDown),
+            map,
+            rooms: Vec::new(),
+            corridors: Vec::new(),
+        })
+    }
+}
+
+impl fmt::Display for Dungeon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_ascii(&GlyphTable::default()))
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::BTreeMap;
+use std::{env, fs, process};
+use dungoxide::dungeon::{
+    Dungeon, DungeonBuildConfig, DungeonBuildError, DungeonConfigBuilder, DungeonSize, RoomSize, TileType
+};
+use dungoxide::{BuilderRegistry, CellularAutomataBuilder, DrunkardWalkBuilder, RoomPlacementBuilder};
+
+const USAGE: &str = "\
+Usage:
+  dungoxide [generate] [OPTIONS]    generate one dungeon
+  dungoxide batch [OPTIONS]         generate many seeds and print stats
+
+Options:
+  -a, --algorithm <NAME>    bsp, room_placement, cellular_automata or drunkard_walk,
+                            short names rooms, caves and drunkard [default: bsp]
+  -s, --size <WxH>          dungeon size [default: 32x32]
+  -r, --room-size <MIN-MAX> room size [default: 5-10]
+      --doors               place doors
+      --stairs              place entrance and exit
+      --seed <N>            seed, first seed for batch [default: random / 0]
+  -f, --format <FORMAT>     ascii, json or png [default: ascii]
+  -o, --output <PATH>       output file, required for png [default: stdout]
+      --tile-size <N>       png pixels per tile [default: 8]
+      --overlays            draw rooms, room ids and corridors on png
+  -n, --count <N>           number of seeds for batch [default: 100]
+  -h, --help                print this help";
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Format {
+    Ascii,
+    Json,
+    Png,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Mode {
+    Generate,
+    Batch,
+}
+
+#[derive(Debug)]
+struct Options {
+    mode: Mode,
+    algorithm: String,
+    config: DungeonBuildConfig,
+    format: Format,
+    output: Option<String>,
+    tile_size: u32,
+    overlays: bool,
+    count: u64,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{USAGE}");
+        return;
+    }
+
+    let registry = registry();
+    let options = match parse_args(&registry, args) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("error: {error}\n\n{USAGE}");
+            process::exit(2);
+        }
+    };
+
+    let result = match options.mode {
+        Mode::Generate => generate(&registry, &options),
+        Mode::Batch => batch(&registry, &options),
+    };
+
+    if let Err(error) = result {
+        eprintln!("error: {error}");
+        process::exit(1);
+    }
+}
+
+/// Algorithms of the crate with short aliases
+fn registry() -> BuilderRegistry {
+    let mut registry = BuilderRegistry::new();
+    registry.register("rooms", || Box::new(RoomPlacementBuilder::new()));
+    registry.register("caves", || Box::new(CellularAutomataBuilder::new()));
+    registry.register("drunkard", || Box::new(DrunkardWalkBuilder::new()));
+    registry
+}
+
+fn parse_args(registry: &BuilderRegistry, args: Vec<String>) -> Result<Options, String> {
+    let mut options = Options {
+        mode: Mode::Generate,
+        algorithm: String::from("bsp"),
+        config: DungeonBuildConfig::default(),
+        format: Format::Ascii,
+        output: None,
+        tile_size: 8,
+        overlays: false,
+        count: 100,
+    };
+
+    let mut args = args.into_iter().peekable();
+    match args.peek().map(String::as_str) {
+        Some("generate") => {
+            args.next();
+        }
+        Some("batch") => {
+            options.mode = Mode::Batch;
+            args.next();
+        }
+        _ => {}
+    }
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {arg}"));
+
+        match arg.as_str() {
+            "-a" | "--algorithm" => {
+                let algorithm = value()?;
+                if !registry.contains(&algorithm) {
+                    let names: Vec<&str> = registry.names().collect();
+                    return Err(format!("unknown algorithm '{algorithm}', expected one of {}", names.join(", ")));
+                }
+                options.algorithm = algorithm;
+            }
+            "-s" | "--size" => {
+                let (width, height) = parse_pair(&value()?, 'x')?;
+                options.config.dungeon_size = DungeonSize { width, height };
+            }
+            "-r" | "--room-size" => {
+                let (min_room_size, max_room_size) = parse_pair(&value()?, '-')?;
+                options.config.room_size = RoomSize { min_room_size, max_room_size };
+            }
+            "--doors" => options.config.should_place_doors = true,
+            "--stairs" => options.config.should_place_stairs = true,
+            "--seed" => options.config.seed = Some(parse_number(&value()?)?),
+            "-f" | "--format" => {
+                options.format = match value()?.as_str() {
+                    "ascii" => Format::Ascii,
+                    "json" => Format::Json,
+                    "png" => Format::Png,
+                    other => return Err(format!("unknown format '{other}', expected ascii, json or png")),
+                }
+            }
+            "-o" | "--output" => options.output = Some(value()?),
+            "--tile-size" => options.tile_size = parse_number(&value()?)?,
+            "--overlays" => options.overlays = true,
+            "-n" | "--count" => options.count = parse_number(&value()?)?,
+            other => return Err(format!("unknown argument '{other}'")),
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("'{value}' is not a valid number"))
+}
+
+fn parse_pair(value: &str, separator: char) -> Result<(usize, usize), String> {
+    let (first, second) = value
+        .split_once(separator)
+        .ok_or_else(|| format!("'{value}' should look like 10{separator}20"))?;
+
+    Ok((parse_number(first)?, parse_number(second)?))
+}
+
+fn build_dungeon(
+    registry: &BuilderRegistry,
+    algorithm: &str,
+    config: DungeonBuildConfig,
+) -> Result<Dungeon, DungeonBuildError> {
+    DungeonConfigBuilder::new()
+        .build_config(config)
+        .build_algorithm(registry.create(algorithm)?)
+        .build()
+}
+
+fn generate(registry: &BuilderRegistry, options: &Options) -> Result<(), String> {
+    let dungeon = build_dungeon(registry, &options.algorithm, options.config).map_err(|error| error.to_string())?;
+
+    match options.format {
+        Format::Ascii => write_text(options, &dungeon.to_ascii(&Default::default())),
+        Format::Json => write_text(options, &to_json(&dungeon)?),
+        Format::Png => write_png(options, &dungeon),
+    }
+}
+
+fn write_text(options: &Options, text: &str) -> Result<(), String> {
+    match &options.output {
+        Some(path) => fs::write(path, text).map_err(|error| format!("failed to write {path}: {error}")),
+        None => {
+            print!("{text}");
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+fn to_json(dungeon: &Dungeon) -> Result<String, String> {
+    serde_json::to_string_pretty(dungeon).map_err(|error| error.to_string())
+}
+
+#[cfg(not(feature = "json"))]
+fn to_json(_dungeon: &Dungeon) -> Result<String, String> {
+    Err(String::from("json output needs `json` feature"))
+}
+
+#[cfg(feature = "image")]
+fn write_png(options: &Options, dungeon: &Dungeon) -> Result<(), String> {
+    use dungoxide::ImageOptions;
+
+    let path = options.output.as_ref().ok_or("png output needs --output path")?;
+    let image_options = match options.overlays {
+        true => ImageOptions::with_overlays(),
+        false => ImageOptions::default(),
+    };
+
+    dungeon
+        .to_image(&ImageOptions { tile_size: options.tile_size, ..image_options })
+        .save(path)
+        .map_err(|error| format!("failed to write {path}: {error}"))
+}
+
+#[cfg(not(feature = "image"))]
+fn write_png(_options: &Options, _dungeon: &Dungeon) -> Result<(), String> {
+    Err(String::from("png output needs `image` feature"))
+}
+
+fn batch(registry: &BuilderRegistry, options: &Options) -> Result<(), String> {
+    if options.count == 0 {
+        return Err(String::from("count should be at least 1"));
+    }
+
+    let first_seed = options.config.seed.unwrap_or(0);
+    let end_seed = first_seed
+        .checked_add(options.count)
+        .ok_or_else(|| format!("seed {first_seed} plus count {} overflows u64", options.count))?;
+    let mut room_counts = Vec::new();
+    let mut floor_ratios = Vec::new();
+    let mut failures: Vec<(u64, DungeonBuildError)> = Vec::new();
+
+    for seed in first_seed..end_seed {
+        let config = DungeonBuildConfig { seed: Some(seed), ..options.config };
+
+        match build_dungeon(registry, &options.algorithm, config) {
+            Ok(dungeon) => {
+                let floor = dungeon.map.iter().filter(|&&tile| tile != TileType::Wall).count();
+                room_counts.push(dungeon.rooms.len() as f64);
+                floor_ratios.push(floor as f64 / dungeon.map.len() as f64);
+            }
+            Err(error) => failures.push((seed, error)),
+        }
+    }
+
+    println!("algorithm:    {}", options.algorithm);
+    println!("seeds:        {first_seed}..{end_seed}");
+    println!(
+        "failures:     {} ({:.1}%)",
+        failures.len(),
+        failures.len() as f64 * 100.0 / options.count as f64
+    );
+    print_stat("room count:", &room_counts, 1);
+    print_stat("floor ratio:", &floor_ratios, 3);
+
+    let mut reasons: BTreeMap<&str, Vec<u64>> = BTreeMap::new();
+    for (seed, error) in &failures {
+        reasons.entry(failure_reason(error)).or_default().push(*seed);
+    }
+    for (reason, seeds) in reasons {
+        println!("  {} x {reason}, first seed {}", seeds.len(), seeds[0]);
+    }
+
+    Ok(())
+}
+
+/// short reason without error fields, so failures of different seeds are grouped together
+fn failure_reason(error: &DungeonBuildError) -> &'static str {
+    match error {
+        DungeonBuildError::InvalidSize(..) => "invalid map size",
+        DungeonBuildError::InvalidRoomSize(..) => "invalid room size",
+        DungeonBuildError::RoomTooLargeForDungeon => "room is larger than map",
+        DungeonBuildError::NoRoomsCreated => "no rooms created",
+        DungeonBuildError::NoBuildAlgorithmProvided => "no build algorithm",
+        DungeonBuildError::Disconnected(..) => "disconnected regions",
+        DungeonBuildError::NoStairsPlace => "no place for stairs",
+        DungeonBuildError::NoLevelsProvided => "no levels",
+        DungeonBuildError::UnknownBuildAlgorithm(..) => "unknown build algorithm",
+    }
+}
+
+fn print_stat(name: &str, values: &[f64], precision: usize) {
+    if values.is_empty() {
+        println!("{name:<13} -");
+        return;
+    }
+
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    println!("{name:<13} min {min:.precision$}, mean {mean:.precision$}, max {max:.precision$}");
+}
@@ -1,20 +1,92 @@
-use std::cmp::{min, max};
-use rand::Rng;
+use std::cmp::{min, Ordering};
+use rand::{Rng, RngCore};
+use crate::corridor::{carve_wide_path, CorridorStyle};
+use crate::doors::place_doors;
 use crate::dungeon::{
-    Dungeon, DungeonBuilder, DungeonBuildConfig, DungeonBuildError,
-    TileType
+    CorridorInfo, Dungeon, DungeonBuilder, DungeonBuildConfig, DungeonBuildError,
+    RoomInfo, TileType
 };
+use crate::grid::Grid;
 
-pub struct BinaryPartitionBuilder;
+/// Recursively splits the map into partitions and places one room into every leaf partition,
+/// rooms of sibling partitions are connected with corridors
+#[derive(Debug, Copy, Clone)]
+pub struct BinaryPartitionBuilder {
+    max_depth: Option<usize>,
+    min_split_ratio: f64,
+    max_split_ratio: f64,
+    room_padding: usize,
+    stop_probability: f64,
+    corridor_style: CorridorStyle,
+    corridor_width: usize,
+}
+
+impl BinaryPartitionBuilder {
+    pub fn new() -> Self {
+        Self {
+            max_depth: None,
+            min_split_ratio: 0.0,
+            max_split_ratio: 1.0,
+            room_padding: 0,
+            stop_probability: 0.5,
+            corridor_style: CorridorStyle::LShaped,
+            corridor_width: 1,
+        }
+    }
+
+    /// partitions are not split deeper than `max_depth`, so there are at most `2^max_depth` rooms
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// split position as fraction of partition size, for example `0.4..0.6` gives regular grid-like layout.
+    /// Both parts are never smaller than min room size
+    pub fn split_ratio(mut self, min_split_ratio: f64, max_split_ratio: f64) -> Self {
+        let min_split_ratio = min_split_ratio.clamp(0.0, 1.0);
+        let max_split_ratio = max_split_ratio.clamp(0.0, 1.0);
+        self.min_split_ratio = min_split_ratio.min(max_split_ratio);
+        self.max_split_ratio = min_split_ratio.max(max_split_ratio);
+        self
+    }
+
+    /// tiles between room and every edge of its partition
+    pub fn room_padding(mut self, room_padding: usize) -> Self {
+        self.room_padding = room_padding;
+        self
+    }
+
+    /// chance to stop splitting partition which is already small enough for a room
+    pub fn stop_probability(mut self, stop_probability: f64) -> Self {
+        self.stop_probability = stop_probability.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn corridor_style(mut self, corridor_style: CorridorStyle) -> Self {
+        self.corridor_style = corridor_style;
+        self
+    }
+
+    pub fn corridor_width(mut self, corridor_width: usize) -> Self {
+        self.corridor_width = corridor_width.max(1);
+        self
+    }
+}
+
+impl Default for BinaryPartitionBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl DungeonBuilder for BinaryPartitionBuilder {
-    fn build(self, build_config: DungeonBuildConfig) -> Result<Dungeon, DungeonBuildError> {
+    fn build(&self, build_config: DungeonBuildConfig, rng: &mut dyn RngCore) -> Result<Dungeon, DungeonBuildError> {
         let width = build_config.dungeon_size.width;
         let height = build_config.dungeon_size.height;
         let room_min_size = build_config.room_size.min_room_size;
         let room_max_size = build_config.room_size.max_room_size;
 
-        let mut map = vec![vec![TileType::Wall; width]; height];
+        let mut map = Grid::new(width, height, TileType::Wall);
         let mut root_node = RoomsPartition::new(Room {
             x: 0,
             y: 0,
@@ -22,10 +94,10 @@ impl DungeonBuilder for BinaryPartitionBuilder {
             height,
         });
 
-        root_node.partition_tree(room_min_size, room_max_size);
+        root_node.partition_tree(self, rng, 0, room_min_size, room_max_size);
 
         let mut rooms = Vec::new();
-        root_node.create_rooms(&mut rooms, room_min_size, room_max_size);
+        root_node.create_rooms(self, rng, &mut rooms, room_min_size, room_max_size);
 
         if rooms.is_empty() {
             return Err(DungeonBuildError::NoRoomsCreated);
@@ -34,18 +106,31 @@ impl DungeonBuilder for BinaryPartitionBuilder {
         for room in &rooms {
             for y in room.y..(room.y + room.height) {
                 for x in room.x..(room.x + room.width) {
-                    map[y][x] = TileType::Floor;
+                    map[(x, y)] = TileType::Floor;
                 }
             }
         }
 
-        root_node.connect_rooms(&mut map);
+        let mut corridors = Vec::new();
+        root_node.connect_rooms(self, rng, &mut map, &rooms, &mut corridors);
+
+        let rooms: Vec<RoomInfo> = rooms
+            .iter()
+            .enumerate()
+            .map(|(id, room)| RoomInfo::new(id, room.x, room.y, room.width, room.height))
+            .collect();
 
         if build_config.should_place_doors {
-            place_doors(&mut map);
+            place_doors(&mut map, &rooms);
         }
 
-        Ok(Dungeon { map })
+        Ok(Dungeon {
+            map,
+            rooms,
+            corridors,
+            entrance: None,
+            exit: None,
+        })
     }
 }
 
@@ -68,7 +153,8 @@ impl Room {
 
 struct RoomsPartition {
     root_room: Room,
-    room: Option<Room>,
+    /// index of the leaf room in the list of created rooms
+    room: Option<usize>,
     left: Option<Box<RoomsPartition>>,
     right: Option<Box<RoomsPartition>>,
 }
@@ -83,32 +169,37 @@ impl RoomsPartition {
         }
     }
 
-    pub fn split(&mut self, min_size: usize) -> bool {
+    pub fn split(&mut self, settings: &BinaryPartitionBuilder, rng: &mut dyn RngCore, min_size: usize) -> bool {
         if self.left.is_some() || self.right.is_some() {
             return false;
         }
 
-        let mut rng = rand::thread_rng();
-
-        let should_split_horizontally = if self.root_room.width >= self.root_room.height {
-            false
-        } else if self.root_room.height >= self.root_room.width {
-            true
-        } else {
-            rng.gen_bool(0.5)
+        let should_split_horizontally = match self.root_room.width.cmp(&self.root_room.height) {
+            Ordering::Greater => false,
+            Ordering::Less => true,
+            Ordering::Equal => rng.gen_bool(0.5),
         };
 
-        let max_size = if should_split_horizontally {
-            self.root_room.height - min_size
+        let size = if should_split_horizontally {
+            self.root_room.height
         } else {
-            self.root_room.width - min_size
+            self.root_room.width
         };
 
-        if max_size <= min_size {
+        // both parts should fit the smallest room with padding, so every leaf gets a room
+        let min_part = min_size + 2 * settings.room_padding + 1;
+
+        // max split is exclusive, so equal ratio bounds still give one split position
+        let min_split = min_part.max((size as f64 * settings.min_split_ratio).round() as usize);
+        let max_split = (size + 1)
+            .saturating_sub(min_part)
+            .min((size as f64 * settings.max_split_ratio).round() as usize + 1);
+
+        if max_split <= min_split {
             return false;
         }
 
-        let split = rng.gen_range(min_size..max_size);
+        let split = rng.gen_range(min_split..max_split);
 
         if should_split_horizontally {
             let left_split = Box::new(RoomsPartition::new(Room {
@@ -149,33 +240,53 @@ impl RoomsPartition {
         true
     }
 
-    pub fn partition_tree(&mut self, min_size: usize, max_size: usize) {
+    pub fn partition_tree(
+        &mut self,
+        settings: &BinaryPartitionBuilder,
+        rng: &mut dyn RngCore,
+        depth: usize,
+        min_size: usize,
+        max_size: usize,
+    ) {
+        if settings.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+            return;
+        }
+
         let can_split = self.root_room.width > max_size
         || self.root_room.height > max_size
-        || rand::thread_rng().gen_bool(0.5);
+        || rng.gen_bool(1.0 - settings.stop_probability);
 
         if !can_split {
             return;
         }
 
-        if self.split(min_size) {
+        if self.split(settings, rng, min_size) {
             if let Some(ref mut left) = self.left {
-                left.partition_tree(min_size, max_size);
+                left.partition_tree(settings, rng, depth + 1, min_size, max_size);
             }
             if let Some(ref mut right) = self.right {
-                right.partition_tree(min_size, max_size);
+                right.partition_tree(settings, rng, depth + 1, min_size, max_size);
             }
         }
     }
 
-    pub fn create_rooms(&mut self, rooms: &mut Vec<Room>, min_size: usize, max_size: usize) {
+    pub fn create_rooms(
+        &mut self,
+        settings: &BinaryPartitionBuilder,
+        rng: &mut dyn RngCore,
+        rooms: &mut Vec<Room>,
+        min_size: usize,
+        max_size: usize,
+    ) {
         let is_left_or_right = self.left.is_some() || self.right.is_some();
 
         if !is_left_or_right {
-            let mut rng = rand::thread_rng();
-
+            let padding = settings.room_padding;
             let (w_min, h_min) = (min_size, min_size);
-            let (w_max, h_max) = (min(self.root_room.width - 1, max_size), min(self.root_room.height - 1, max_size));
+            let (w_max, h_max) = (
+                min(self.root_room.width.saturating_sub(2 * padding + 1), max_size),
+                min(self.root_room.height.saturating_sub(2 * padding + 1), max_size),
+            );
 
             if w_min > w_max || h_min > h_max {
                 return;
@@ -186,15 +297,12 @@ impl RoomsPartition {
             let room_h =
                 rng.gen_range(h_min..=h_max);
 
-            if room_w > self.root_room.x + self.root_room.width
-            || room_h > self.root_room.y + self.root_room.height {
-                return;
-            }
-
-            let room_x =
-                rng.gen_range(self.root_room.x..=(self.root_room.x + self.root_room.width - room_w));
-            let room_y =
-                rng.gen_range(self.root_room.y..=(self.root_room.y + self.root_room.height - room_h));
+            let room_x = rng.gen_range(
+                (self.root_room.x + padding)..=(self.root_room.x + self.root_room.width - padding - room_w)
+            );
+            let room_y = rng.gen_range(
+                (self.root_room.y + padding)..=(self.root_room.y + self.root_room.height - padding - room_h)
+            );
 
             let room = Room {
                 x: room_x,
@@ -202,110 +310,82 @@ impl RoomsPartition {
                 width: room_w,
                 height: room_h,
             };
-            self.room = Some(room);
+            self.room = Some(rooms.len());
             rooms.push(room);
 
             return;
         }
 
         if let Some(ref mut left) = self.left {
-            left.create_rooms(rooms, min_size, max_size);
+            left.create_rooms(settings, rng, rooms, min_size, max_size);
         }
 
         if let Some(ref mut right) = self.right {
-            right.create_rooms(rooms, min_size, max_size);
+            right.create_rooms(settings, rng, rooms, min_size, max_size);
         }
     }
 
-    pub fn connect_rooms(&self, map: &mut Vec<Vec<TileType>>) {
+    pub fn connect_rooms(
+        &self,
+        settings: &BinaryPartitionBuilder,
+        rng: &mut dyn RngCore,
+        map: &mut Grid<TileType>,
+        rooms: &[Room],
+        corridors: &mut Vec<CorridorInfo>,
+    ) {
         if let Some(ref left) = self.left {
-            left.connect_rooms(map);
+            left.connect_rooms(settings, rng, map, rooms, corridors);
         }
 
         if let Some(ref right) = self.right {
-            right.connect_rooms(map);
+            right.connect_rooms(settings, rng, map, rooms, corridors);
         }
 
-        if self.left.is_some() && self.right.is_some() {
-            let left_center = self.left.as_ref().unwrap().get_room_center();
-            let right_center = self.right.as_ref().unwrap().get_room_center();
-
-            if let (Some((left_x, left_y)), Some((right_x, right_y))) = (left_center, right_center) {
-                apply_corridors(map, left_x, left_y, right_x, right_y);
+        if let (Some(left), Some(right)) = (&self.left, &self.right) {
+            let mut left_ids = Vec::new();
+            let mut right_ids = Vec::new();
+            left.room_ids(&mut left_ids);
+            right.room_ids(&mut right_ids);
+
+            // closest rooms of the two subtrees are the connection points of this partition
+            let closest = left_ids
+                .iter()
+                .flat_map(|&left_id| right_ids.iter().map(move |&right_id| (left_id, right_id)))
+                .min_by_key(|&(left_id, right_id)| {
+                    let (x1, y1) = rooms[left_id].center();
+                    let (x2, y2) = rooms[right_id].center();
+                    x1.abs_diff(x2).pow(2) + y1.abs_diff(y2).pow(2)
+                });
+
+            if let Some((left_id, right_id)) = closest {
+                let path = apply_corridors(settings, map, rng, rooms[left_id].center(), rooms[right_id].center());
+                corridors.push(CorridorInfo::new(left_id, right_id, path));
             }
         }
     }
 
-    pub fn get_room_center(&self) -> Option<(usize, usize)> {
-        if let Some(ref room) = self.room {
-            return Some(room.center());
-        }
+    /// ids of all rooms created in this partition and its children
+    pub fn room_ids(&self, ids: &mut Vec<usize>) {
+        ids.extend(self.room);
 
-        self.left
-            .as_ref()
-            .and_then(|l| l.get_room_center())
-            .or_else(|| self.right.as_ref().and_then(|r| r.get_room_center()))
-    }
-}
-
-fn apply_corridors(
-    map: &mut [Vec<TileType>],
-    x1: usize,
-    y1: usize,
-    x2: usize,
-    y2: usize,
-) {
-    let mut rng = rand::thread_rng();
-
-    if rng.gen_bool(0.5) {
-        for x in min(x1, x2)..=max(x1, x2) {
-            if map[y1][x] == TileType::Wall {
-                map[y1][x] = TileType::Floor;
-            }
-        }
-        for y in min(y1, y2)..=max(y1, y2) {
-            if map[y][x2] == TileType::Wall {
-                map[y][x2] = TileType::Floor;
-            }
-        }
-    } else {
-        for y in min(y1, y2)..=max(y1, y2) {
-            if map[y][x1] == TileType::Wall {
-                map[y][x1] = TileType::Floor;
-            }
+        if let Some(ref left) = self.left {
+            left.room_ids(ids);
         }
-        for x in min(x1, x2)..=max(x1, x2) {
-            if map[y2][x] == TileType::Wall {
-                map[y2][x] = TileType::Floor;
-            }
+
+        if let Some(ref right) = self.right {
+            right.room_ids(ids);
         }
     }
 }
 
-/// algo to place doors outside of rooms
-/// this algo doesn't work correctly, but I didn't have time to fix it
-fn place_doors(map: &mut [Vec<TileType>]) {
-    let height = map.len();
-    let width = map[0].len();
-
-    for y in 1..(height - 1) {
-        for x in 1..(width - 1) {
-            if map[y][x] == TileType::Wall {
-                let adjacent_floors = [
-                    map[y - 1][x],
-                    map[y + 1][x],
-                    map[y][x - 1],
-                    map[y][x + 1],
-                ];
-                let floor_count = adjacent_floors
-                    .iter()
-                    .filter(|&&tile| tile == TileType::Floor)
-                    .count();
-
-                if floor_count >= 2 {
-                    map[y][x] = TileType::Door;
-                }
-            }
-        }
-    }
+fn apply_corridors(
+    settings: &BinaryPartitionBuilder,
+    map: &mut Grid<TileType>,
+    rng: &mut dyn RngCore,
+    start: (usize, usize),
+    end: (usize, usize),
+) -> Vec<(usize, usize)> {
+    let path = settings.corridor_style.path(start, end, rng.gen_bool(0.5));
+    carve_wide_path(map, &path, settings.corridor_width);
+    path
 }
@@ -0,0 +1,169 @@
+use rand::{Rng, RngCore};
+use crate::connectivity::{closest_tiles, find_regions};
+use crate::corridor::{carve_path, l_shaped_path};
+use crate::dungeon::{
+    Dungeon, DungeonBuilder, DungeonBuildConfig, DungeonBuildError,
+    TileType
+};
+use crate::grid::Grid;
+
+/// What to do with caverns that are not connected to the largest one
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CavernHandling {
+    /// fill smaller caverns with walls
+    KeepLargest,
+    /// dig tunnels from smaller caverns to the largest one
+    Connect,
+}
+
+/// Cave generation using cellular automata.
+/// Map is filled with random noise and smoothed with birth/survival rule,
+/// room size config is not used, doors are never placed
+#[derive(Debug, Copy, Clone)]
+pub struct CellularAutomataBuilder {
+    wall_density: f64,
+    smoothing_passes: usize,
+    birth_limit: usize,
+    survival_limit: usize,
+    min_cavern_size: usize,
+    cavern_handling: CavernHandling,
+}
+
+impl CellularAutomataBuilder {
+    pub fn new() -> Self {
+        Self {
+            wall_density: 0.45,
+            smoothing_passes: 5,
+            birth_limit: 5,
+            survival_limit: 4,
+            min_cavern_size: 10,
+            cavern_handling: CavernHandling::Connect,
+        }
+    }
+
+    /// chance of every tile to be a wall in the initial noise
+    pub fn wall_density(mut self, wall_density: f64) -> Self {
+        self.wall_density = wall_density.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn smoothing_passes(mut self, smoothing_passes: usize) -> Self {
+        self.smoothing_passes = smoothing_passes;
+        self
+    }
+
+    /// floor becomes wall when it has at least `birth_limit` wall neighbours
+    pub fn birth_limit(mut self, birth_limit: usize) -> Self {
+        self.birth_limit = birth_limit;
+        self
+    }
+
+    /// wall stays wall when it has at least `survival_limit` wall neighbours
+    pub fn survival_limit(mut self, survival_limit: usize) -> Self {
+        self.survival_limit = survival_limit;
+        self
+    }
+
+    /// caverns smaller than this are filled with walls
+    pub fn min_cavern_size(mut self, min_cavern_size: usize) -> Self {
+        self.min_cavern_size = min_cavern_size;
+        self
+    }
+
+    pub fn cavern_handling(mut self, cavern_handling: CavernHandling) -> Self {
+        self.cavern_handling = cavern_handling;
+        self
+    }
+
+    fn smooth(&self, map: &Grid<TileType>) -> Grid<TileType> {
+        let mut next = map.clone();
+
+        for y in 1..(map.height() - 1) {
+            for x in 1..(map.width() - 1) {
+                let walls = map.neighbors8(x, y)
+                    .filter(|&neighbour| map[neighbour] == TileType::Wall)
+                    .count();
+                let limit = if map[(x, y)] == TileType::Wall {
+                    self.survival_limit
+                } else {
+                    self.birth_limit
+                };
+
+                next[(x, y)] = if walls >= limit { TileType::Wall } else { TileType::Floor };
+            }
+        }
+
+        next
+    }
+}
+
+impl DungeonBuilder for CellularAutomataBuilder {
+    fn build(&self, build_config: DungeonBuildConfig, rng: &mut dyn RngCore) -> Result<Dungeon, DungeonBuildError> {
+        let width = build_config.dungeon_size.width;
+        let height = build_config.dungeon_size.height;
+
+        if width < 3 || height < 3 {
+            return Err(DungeonBuildError::NoRoomsCreated);
+        }
+
+        let mut map = Grid::new(width, height, TileType::Wall);
+        for y in 1..(height - 1) {
+            for x in 1..(width - 1) {
+                if !rng.gen_bool(self.wall_density) {
+                    map[(x, y)] = TileType::Floor;
+                }
+            }
+        }
+
+        for _ in 0..self.smoothing_passes {
+            map = self.smooth(&map);
+        }
+
+        let mut regions = find_regions(&map);
+        regions.retain(|region| {
+            let keep = region.len() >= self.min_cavern_size;
+            if !keep {
+                fill_region(&mut map, region, TileType::Wall);
+            }
+            keep
+        });
+
+        if regions.is_empty() {
+            return Err(DungeonBuildError::NoRoomsCreated);
+        }
+
+        let mut connected = regions.remove(0);
+        for region in regions {
+            match self.cavern_handling {
+                CavernHandling::KeepLargest => fill_region(&mut map, &region, TileType::Wall),
+                CavernHandling::Connect => {
+                    let (from, to) = closest_tiles(&region, &connected);
+                    let path = l_shaped_path(from, to, rng.gen_bool(0.5));
+                    carve_path(&mut map, &path);
+                    connected.extend(path);
+                    connected.extend(region);
+                }
+            }
+        }
+
+        Ok(Dungeon {
+            map,
+            rooms: Vec::new(),
+            corridors: Vec::new(),
+            entrance: None,
+            exit: None,
+        })
+    }
+}
+
+impl Default for CellularAutomataBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn fill_region(map: &mut Grid<TileType>, region: &[(usize, usize)], tile: TileType) {
+    for &position in region {
+        map[position] = tile;
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::VecDeque;
+use rand::{Rng, RngCore};
+use crate::corridor::{carve_path, l_shaped_path};
+use crate::dungeon::{ConnectivityPolicy, CorridorInfo, Dungeon, DungeonBuildError, TileType};
+use crate::grid::Grid;
+
+/// Checks that every walkable tile is reachable from any other one.
+/// Isolated regions are either joined to the largest region or reported as an error
+pub(crate) fn ensure_connected(
+    dungeon: &mut Dungeon,
+    policy: ConnectivityPolicy,
+    rng: &mut dyn RngCore,
+) -> Result<(), DungeonBuildError> {
+    let mut regions = find_regions(&dungeon.map);
+    if regions.len() <= 1 {
+        return Ok(());
+    }
+
+    if policy == ConnectivityPolicy::Fail {
+        return Err(DungeonBuildError::Disconnected(regions.iter().map(|r| r.len()).collect()));
+    }
+
+    let mut connected = regions.remove(0);
+    for region in regions {
+        let path = match closest_rooms(dungeon, &region, &connected) {
+            Some((from_room, to_room)) => {
+                let path = l_shaped_path(
+                    dungeon.rooms[from_room].center,
+                    dungeon.rooms[to_room].center,
+                    rng.gen_bool(0.5),
+                );
+                dungeon.corridors.push(CorridorInfo::new(from_room, to_room, path.clone()));
+                path
+            }
+            None => {
+                let (from, to) = closest_tiles(&region, &connected);
+                l_shaped_path(from, to, rng.gen_bool(0.5))
+            }
+        };
+
+        carve_path(&mut dungeon.map, &path);
+        connected.extend(path);
+        connected.extend(region);
+    }
+
+    Ok(())
+}
+
+/// Pair of rooms with the closest centers, one from each region
+fn closest_rooms(
+    dungeon: &Dungeon,
+    from: &[(usize, usize)],
+    to: &[(usize, usize)],
+) -> Option<(usize, usize)> {
+    let rooms_in = |region: &[(usize, usize)]| -> Vec<usize> {
+        dungeon.rooms
+            .iter()
+            .filter(|room| region.contains(&room.center))
+            .map(|room| room.id)
+            .collect()
+    };
+    let from_rooms = rooms_in(from);
+    let to_rooms = rooms_in(to);
+
+    from_rooms
+        .iter()
+        .flat_map(|&a| to_rooms.iter().map(move |&b| (a, b)))
+        .min_by_key(|&(a, b)| {
+            let (ax, ay) = dungeon.rooms[a].center;
+            let (bx, by) = dungeon.rooms[b].center;
+            ax.abs_diff(bx) + ay.abs_diff(by)
+        })
+}
+
+/// Splits walkable tiles into 4-connected regions, sorted from the largest to the smallest
+pub(crate) fn find_regions(map: &Grid<TileType>) -> Vec<Vec<(usize, usize)>> {
+    let mut visited = Grid::new(map.width(), map.height(), false);
+    let mut regions = Vec::new();
+
+    for position in map.positions() {
+        if visited[position] || !map[position].is_walkable() {
+            continue;
+        }
+
+        let mut region = Vec::new();
+        let mut queue = VecDeque::from([position]);
+        visited[position] = true;
+
+        while let Some((x, y)) = queue.pop_front() {
+            region.push((x, y));
+
+            for neighbor in map.neighbors4(x, y) {
+                if !visited[neighbor] && map[neighbor].is_walkable() {
+                    visited[neighbor] = true;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        regions.push(region);
+    }
+
+    regions.sort_by_key(|region| std::cmp::Reverse(region.len()));
+    regions
+}
+
+/// Pair of closest tiles between two regions by manhattan distance
+pub(crate) fn closest_tiles(
+    from: &[(usize, usize)],
+    to: &[(usize, usize)],
+) -> ((usize, usize), (usize, usize)) {
+    let mut best = (from[0], to[0]);
+    let mut best_distance = usize::MAX;
+
+    for &a in from {
+        for &b in to {
+            let distance = a.0.abs_diff(b.0) + a.1.abs_diff(b.1);
+            if distance < best_distance {
+                best_distance = distance;
+                best = (a, b);
+            }
+        }
+    }
+
+    best
+}
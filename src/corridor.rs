@@ -0,0 +1,167 @@
+use crate::dungeon::{Dungeon, TileType};
+use crate::grid::Grid;
+
+/// Shape of corridors dug between rooms
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CorridorStyle {
+    /// one turn, direction of the first leg is random
+    LShaped,
+    /// two turns, corridor changes direction in the middle between rooms
+    ZShaped,
+}
+
+impl CorridorStyle {
+    pub(crate) fn path(
+        &self,
+        start: (usize, usize),
+        end: (usize, usize),
+        horizontal_first: bool,
+    ) -> Vec<(usize, usize)> {
+        match self {
+            CorridorStyle::LShaped => l_shaped_path(start, end, horizontal_first),
+            CorridorStyle::ZShaped => z_shaped_path(start, end, horizontal_first),
+        }
+    }
+}
+
+/// L-shaped path between two points, ordered from start to end
+pub(crate) fn l_shaped_path(
+    start: (usize, usize),
+    end: (usize, usize),
+    horizontal_first: bool,
+) -> Vec<(usize, usize)> {
+    let (x1, y1) = start;
+    let (x2, y2) = end;
+    let corner = if horizontal_first { (x2, y1) } else { (x1, y2) };
+
+    let mut path = straight_path(start, corner);
+    path.extend(straight_path(corner, end).into_iter().skip(1));
+    path
+}
+
+/// Path with two turns in the middle between points, ordered from start to end
+pub(crate) fn z_shaped_path(
+    start: (usize, usize),
+    end: (usize, usize),
+    horizontal_first: bool,
+) -> Vec<(usize, usize)> {
+    let (x1, y1) = start;
+    let (x2, y2) = end;
+    let (first, second) = if horizontal_first {
+        let middle = (x1 + x2) / 2;
+        ((middle, y1), (middle, y2))
+    } else {
+        let middle = (y1 + y2) / 2;
+        ((x1, middle), (x2, middle))
+    };
+
+    let mut path = straight_path(start, first);
+    path.extend(straight_path(first, second).into_iter().skip(1));
+    path.extend(straight_path(second, end).into_iter().skip(1));
+    path
+}
+
+/// straight horizontal or vertical line including both ends
+fn straight_path(start: (usize, usize), end: (usize, usize)) -> Vec<(usize, usize)> {
+    let (x1, y1) = start;
+    let (x2, y2) = end;
+
+    if y1 == y2 {
+        step_range(x1, x2).map(|x| (x, y1)).collect()
+    } else {
+        step_range(y1, y2).map(|y| (x1, y)).collect()
+    }
+}
+
+fn step_range(from: usize, to: usize) -> Box<dyn Iterator<Item = usize>> {
+    if from <= to {
+        Box::new(from..=to)
+    } else {
+        Box::new((to..=from).rev())
+    }
+}
+
+/// turns walls on the path into floor, other tiles are kept as is
+pub(crate) fn carve_path(map: &mut Grid<TileType>, path: &[(usize, usize)]) {
+    for &(x, y) in path {
+        if map[(x, y)] == TileType::Wall {
+            map[(x, y)] = TileType::Floor;
+        }
+    }
+}
+
+/// Carves square brush of `width` tiles centered on every tile of the path,
+/// border of the map is never carved
+pub(crate) fn carve_wide_path(map: &mut Grid<TileType>, path: &[(usize, usize)], width: usize) {
+    carve_path(map, path);
+
+    let before = (width.max(1) - 1) / 2;
+    let after = width.max(1) - 1 - before;
+    let (max_x, max_y) = (map.width().saturating_sub(2), map.height().saturating_sub(2));
+
+    for &(x, y) in path {
+        for ny in y.saturating_sub(before).max(1)..=(y + after).min(max_y) {
+            for nx in x.saturating_sub(before).max(1)..=(x + after).min(max_x) {
+                if map[(nx, ny)] == TileType::Wall {
+                    map[(nx, ny)] = TileType::Floor;
+                }
+            }
+        }
+    }
+}
+
+/// Part of a corridor between two rooms, corridor crossing other rooms on the way
+/// is split into one segment per pair of consecutive rooms
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct CorridorSegment {
+    pub from: usize,
+    pub to: usize,
+    /// index in `Dungeon::corridors`
+    pub corridor: usize,
+    /// number of corridor tiles outside of rooms
+    pub length: usize,
+}
+
+/// Segments of all corridors in corridor order, corridors with unknown rooms are skipped
+pub(crate) fn corridor_segments(dungeon: &Dungeon) -> Vec<CorridorSegment> {
+    let mut room_at = Grid::new(dungeon.map.width(), dungeon.map.height(), None);
+    for (id, room) in dungeon.rooms.iter().enumerate() {
+        for y in room.y..(room.y + room.height) {
+            for x in room.x..(room.x + room.width) {
+                if let Some(cell) = room_at.get_mut(x, y) {
+                    *cell = Some(id);
+                }
+            }
+        }
+    }
+
+    let mut segments = Vec::new();
+    for (i, corridor) in dungeon.corridors.iter().enumerate() {
+        let room_count = dungeon.rooms.len();
+        if corridor.from_room >= room_count || corridor.to_room >= room_count {
+            continue;
+        }
+
+        let mut from = corridor.from_room;
+        let mut length = 0;
+        // corridor always ends in `to_room`, even when the path stops next to it
+        let rooms = corridor.path
+            .iter()
+            .map(|&(x, y)| room_at.get(x, y).copied().flatten())
+            .chain([Some(corridor.to_room)]);
+
+        for room in rooms {
+            match room {
+                None => length += 1,
+                Some(room) if room == from => length = 0,
+                Some(room) => {
+                    segments.push(CorridorSegment { from, to: room, corridor: i, length });
+                    from = room;
+                    length = 0;
+                }
+            }
+        }
+    }
+
+    segments
+}
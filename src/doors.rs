@@ -0,0 +1,70 @@
+use crate::dungeon::{RoomInfo, TileType};
+use crate::grid::Grid;
+
+/// Places doors where corridors cross the perimeter of the rooms.
+/// Perimeter is a ring of tiles right outside of the room bounds, every run of floor
+/// tiles on the ring is one threshold. Door is placed only when threshold is one tile wide
+/// and it is a chokepoint: walls on both sides, floor in front and behind
+pub(crate) fn place_doors(map: &mut Grid<TileType>, rooms: &[RoomInfo]) {
+    for room in rooms {
+        for side in perimeter_sides(map, room) {
+            for threshold in thresholds(map, &side) {
+                if let [(x, y)] = threshold[..] {
+                    if is_chokepoint(map, x, y) && !rooms.iter().any(|r| r.contains(x, y)) {
+                        map[(x, y)] = TileType::Door;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// tiles on every side of the ring around the room, corners are not included
+fn perimeter_sides(map: &Grid<TileType>, room: &RoomInfo) -> Vec<Vec<(usize, usize)>> {
+    let height = map.height();
+    let width = map.width();
+    let mut sides = Vec::new();
+
+    if room.y > 0 {
+        sides.push((room.x..(room.x + room.width)).map(|x| (x, room.y - 1)).collect());
+    }
+    if room.y + room.height < height {
+        sides.push((room.x..(room.x + room.width)).map(|x| (x, room.y + room.height)).collect());
+    }
+    if room.x > 0 {
+        sides.push((room.y..(room.y + room.height)).map(|y| (room.x - 1, y)).collect());
+    }
+    if room.x + room.width < width {
+        sides.push((room.y..(room.y + room.height)).map(|y| (room.x + room.width, y)).collect());
+    }
+
+    sides
+}
+
+/// splits side into runs of walkable tiles
+fn thresholds(map: &Grid<TileType>, side: &[(usize, usize)]) -> Vec<Vec<(usize, usize)>> {
+    side.split(|&position| !map[position].is_walkable())
+        .filter(|run| !run.is_empty())
+        .map(|run| run.to_vec())
+        .collect()
+}
+
+/// walls on two opposite sides and floor on the other two
+pub(crate) fn is_chokepoint(map: &Grid<TileType>, x: usize, y: usize) -> bool {
+    let height = map.height();
+    let width = map.width();
+
+    if x == 0 || y == 0 || x + 1 >= width || y + 1 >= height {
+        return false;
+    }
+
+    let (left, right) = (map[(x - 1, y)], map[(x + 1, y)]);
+    let (up, down) = (map[(x, y - 1)], map[(x, y + 1)]);
+
+    let vertical_passage = left == TileType::Wall && right == TileType::Wall
+        && up == TileType::Floor && down == TileType::Floor;
+    let horizontal_passage = up == TileType::Wall && down == TileType::Wall
+        && left == TileType::Floor && right == TileType::Floor;
+
+    vertical_passage || horizontal_passage
+}
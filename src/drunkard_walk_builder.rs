@@ -0,0 +1,173 @@
+use rand::{Rng, RngCore};
+use crate::dungeon::{
+    Dungeon, DungeonBuilder, DungeonBuildConfig, DungeonBuildError,
+    TileType
+};
+use crate::grid::Grid;
+
+/// Where every next walker starts
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SpawnMode {
+    Center,
+    RandomFloor,
+    /// from the tile where previous walker stopped
+    LastPosition,
+}
+
+/// Mirrors every carved tile, so the map is symmetric along chosen axes
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Symmetry {
+    None,
+    /// left and right halves are mirrored
+    Horizontal,
+    /// top and bottom halves are mirrored
+    Vertical,
+    Both,
+}
+
+/// Carves floor with random walkers until target floor percentage is reached,
+/// room size config is not used, doors are never placed
+#[derive(Debug, Copy, Clone)]
+pub struct DrunkardWalkBuilder {
+    floor_percent: f64,
+    spawn_mode: SpawnMode,
+    max_steps: usize,
+    max_walkers: usize,
+    symmetry: Symmetry,
+}
+
+impl DrunkardWalkBuilder {
+    pub fn new() -> Self {
+        Self {
+            floor_percent: 0.4,
+            spawn_mode: SpawnMode::Center,
+            max_steps: 400,
+            max_walkers: 1000,
+            symmetry: Symmetry::None,
+        }
+    }
+
+    /// part of the map that should become floor, from 0.0 to 1.0
+    pub fn floor_percent(mut self, floor_percent: f64) -> Self {
+        self.floor_percent = floor_percent.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn spawn_mode(mut self, spawn_mode: SpawnMode) -> Self {
+        self.spawn_mode = spawn_mode;
+        self
+    }
+
+    /// how many steps every walker does before it stops
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// generation stops after this many walkers even if target is not reached
+    pub fn max_walkers(mut self, max_walkers: usize) -> Self {
+        self.max_walkers = max_walkers;
+        self
+    }
+
+    pub fn symmetry(mut self, symmetry: Symmetry) -> Self {
+        self.symmetry = symmetry;
+        self
+    }
+
+    /// carves tile with its mirrors, returns new floor tiles
+    pub(crate) fn carve(&self, map: &mut Grid<TileType>, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let height = map.height();
+        let width = map.width();
+        let mirrored_x = width - 1 - x;
+        let mirrored_y = height - 1 - y;
+
+        let tiles = match self.symmetry {
+            Symmetry::None => vec![(x, y)],
+            Symmetry::Horizontal => vec![(x, y), (mirrored_x, y)],
+            Symmetry::Vertical => vec![(x, y), (x, mirrored_y)],
+            Symmetry::Both => vec![(x, y), (mirrored_x, y), (x, mirrored_y), (mirrored_x, mirrored_y)],
+        };
+
+        let mut carved = Vec::new();
+        for (tx, ty) in tiles {
+            if map[(tx, ty)] == TileType::Wall {
+                map[(tx, ty)] = TileType::Floor;
+                carved.push((tx, ty));
+            }
+        }
+        carved
+    }
+}
+
+impl DungeonBuilder for DrunkardWalkBuilder {
+    fn build(&self, build_config: DungeonBuildConfig, rng: &mut dyn RngCore) -> Result<Dungeon, DungeonBuildError> {
+        let width = build_config.dungeon_size.width;
+        let height = build_config.dungeon_size.height;
+
+        if width < 3 || height < 3 {
+            return Err(DungeonBuildError::NoRoomsCreated);
+        }
+
+        let mut map = Grid::new(width, height, TileType::Wall);
+        let target_floor = (self.floor_percent * (width * height) as f64).ceil() as usize;
+        let center = (width / 2, height / 2);
+
+        let mut floor_tiles = Vec::new();
+        let mut last_position = center;
+
+        for walker in 0..self.max_walkers {
+            if floor_tiles.len() >= target_floor {
+                break;
+            }
+
+            let (mut x, mut y) = if walker == 0 {
+                center
+            } else {
+                match self.spawn_mode {
+                    SpawnMode::Center => center,
+                    // nothing is carved yet when walkers make no steps
+                    SpawnMode::RandomFloor if floor_tiles.is_empty() => last_position,
+                    SpawnMode::RandomFloor => floor_tiles[rng.gen_range(0..floor_tiles.len())],
+                    SpawnMode::LastPosition => last_position,
+                }
+            };
+
+            for _ in 0..self.max_steps {
+                floor_tiles.extend(self.carve(&mut map, x, y));
+
+                if floor_tiles.len() >= target_floor {
+                    break;
+                }
+
+                match rng.gen_range(0..4) {
+                    0 if x > 1 => x -= 1,
+                    1 if x < width - 2 => x += 1,
+                    2 if y > 1 => y -= 1,
+                    3 if y < height - 2 => y += 1,
+                    _ => {}
+                }
+            }
+
+            last_position = (x, y);
+        }
+
+        if floor_tiles.is_empty() {
+            return Err(DungeonBuildError::NoRoomsCreated);
+        }
+
+        Ok(Dungeon {
+            map,
+            rooms: Vec::new(),
+            corridors: Vec::new(),
+            entrance: None,
+            exit: None,
+        })
+    }
+}
+
+impl Default for DrunkardWalkBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,20 +1,118 @@
 use justerror::Error;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use crate::connectivity::ensure_connected;
+use crate::grid::Grid;
+use crate::room_tags::RoomTag;
+use crate::stairs::place_stairs;
 
+/// Every random decision of the algorithm should be taken from `rng`,
+/// so the same config and rng state always produce the same dungeon.
+/// Trait is object safe, so algorithm can be picked at runtime as `Box<dyn DungeonBuilder>`
 pub trait DungeonBuilder {
-    fn build(self, build_config: DungeonBuildConfig) -> Result<Dungeon, DungeonBuildError>;
+    fn build(&self, build_config: DungeonBuildConfig, rng: &mut dyn RngCore) -> Result<Dungeon, DungeonBuildError>;
+}
+
+impl<BuilderAlgorithm: DungeonBuilder + ?Sized> DungeonBuilder for Box<BuilderAlgorithm> {
+    fn build(&self, build_config: DungeonBuildConfig, rng: &mut dyn RngCore) -> Result<Dungeon, DungeonBuildError> {
+        (**self).build(build_config, rng)
+    }
+}
+
+impl<BuilderAlgorithm: DungeonBuilder + ?Sized> DungeonBuilder for &BuilderAlgorithm {
+    fn build(&self, build_config: DungeonBuildConfig, rng: &mut dyn RngCore) -> Result<Dungeon, DungeonBuildError> {
+        (**self).build(build_config, rng)
+    }
 }
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TileType {
+    StairsUp = 0,
     Door = 1,
+    StairsDown = 2,
+    /// door which is closed until its key is found, see `LockPlacer`
+    LockedDoor = 3,
     Wall = 4,
     Floor = 5,
 }
 
-#[derive(Debug, Clone)]
+impl TileType {
+    /// locked door is not walkable until it is opened
+    pub fn is_walkable(&self) -> bool {
+        matches!(self, TileType::Floor | TileType::Door | TileType::StairsUp | TileType::StairsDown)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Dungeon {
-    pub map: Vec<Vec<TileType>>,
+    pub map: Grid<TileType>,
+    pub rooms: Vec<RoomInfo>,
+    pub corridors: Vec<CorridorInfo>,
+    /// position of `TileType::StairsUp` when stairs are placed
+    pub entrance: Option<(usize, usize)>,
+    /// position of `TileType::StairsDown` when stairs are placed
+    pub exit: Option<(usize, usize)>,
+}
+
+/// Room rectangle produced by the build algorithm, `id` is an index in `Dungeon::rooms`
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoomInfo {
+    pub id: usize,
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub center: (usize, usize),
+    /// assigned by `RoomTagger`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tag: Option<RoomTag>,
+}
+
+impl RoomInfo {
+    pub fn new(id: usize, x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            id,
+            x,
+            y,
+            width,
+            height,
+            center: (x + width / 2, y + height / 2),
+            tag: None,
+        }
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// Corridor between two rooms, `path` contains every tile from `start` to `end`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CorridorInfo {
+    pub from_room: usize,
+    pub to_room: usize,
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+    pub path: Vec<(usize, usize)>,
+}
+
+impl CorridorInfo {
+    /// Panics when `path` is empty, `start` and `end` are its first and last tiles
+    pub fn new(from_room: usize, to_room: usize, path: Vec<(usize, usize)>) -> Self {
+        assert!(!path.is_empty(), "Corridor path should contain at least one tile");
+        Self {
+            from_room,
+            to_room,
+            start: path[0],
+            end: path[path.len() - 1],
+            path,
+        }
+    }
 }
 
 #[Error(desc = "Dungeon generation error", fmt = debug)]
@@ -28,10 +126,19 @@ pub enum DungeonBuildError {
     #[error(desc = "No room was created, check configurations or try one more time", fmt = display)]
     NoRoomsCreated,
     #[error(desc = "Provide build algorithm for dungeon generation", fmt = display)]
-    NoBuildAlgorithmProvided
+    NoBuildAlgorithmProvided,
+    #[error(desc = "Dungeon has isolated regions, sizes of regions: {0:?}", fmt = display)]
+    Disconnected(#[fmt(debug)] Vec<usize>),
+    #[error(desc = "Not enough reachable tiles to place entrance and exit", fmt = display)]
+    NoStairsPlace,
+    #[error(desc = "Provide at least one level for dungeon stack", fmt = display)]
+    NoLevelsProvided,
+    #[error(desc = "No build algorithm registered with name '{0}'", fmt = display)]
+    UnknownBuildAlgorithm(String),
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DungeonSize {
     pub width: usize,
     pub height: usize
@@ -56,6 +163,7 @@ impl DungeonSize {
 }
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RoomSize {
     pub min_room_size: usize,
     pub max_room_size: usize
@@ -75,11 +183,25 @@ impl RoomSize {
     }
 }
 
+/// What to do when generated dungeon has walkable regions not reachable from each other
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConnectivityPolicy {
+    /// dig corridors between isolated regions
+    Repair,
+    /// return `DungeonBuildError::Disconnected` with sizes of the regions
+    Fail,
+}
+
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
 pub struct DungeonBuildConfig {
     pub dungeon_size: DungeonSize,
     pub room_size: RoomSize,
     pub should_place_doors: bool,
+    pub seed: Option<u64>,
+    pub connectivity: ConnectivityPolicy,
+    pub should_place_stairs: bool,
 }
 
 #[derive(Debug)]
@@ -96,6 +218,12 @@ impl<BuilderAlgorithm: DungeonBuilder> DungeonConfigBuilder<BuilderAlgorithm> {
         }
     }
 
+    /// Replaces all parameters with provided config
+    pub fn build_config(mut self, build_config: DungeonBuildConfig) -> Self {
+        self.dungeon_config = build_config;
+        self
+    }
+
     pub fn dungeon_size(mut self, dungeon_size: DungeonSize) -> Self {
         self.dungeon_config.dungeon_size = dungeon_size;
         self
@@ -116,7 +244,34 @@ impl<BuilderAlgorithm: DungeonBuilder> DungeonConfigBuilder<BuilderAlgorithm> {
         self
     }
 
+    /// Places entrance and exit in different rooms as far from each other as possible
+    pub fn should_place_stairs(mut self, should_place_stairs: bool) -> Self {
+        self.dungeon_config.should_place_stairs = should_place_stairs;
+        self
+    }
+
+    pub fn connectivity(mut self, connectivity: ConnectivityPolicy) -> Self {
+        self.dungeon_config.connectivity = connectivity;
+        self
+    }
+
+    /// Same seed with the same config always produces the same dungeon
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.dungeon_config.seed = Some(seed);
+        self
+    }
+
     pub fn build(self) -> Result<Dungeon, DungeonBuildError> {
+        let mut rng = match self.dungeon_config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        self.build_with_rng(&mut rng)
+    }
+
+    /// Builds dungeon using provided rng, configured seed is ignored
+    pub fn build_with_rng(self, rng: &mut dyn RngCore) -> Result<Dungeon, DungeonBuildError> {
         let build_algorithm = self.build_algorithm
             .ok_or(DungeonBuildError::NoBuildAlgorithmProvided)?;
 
@@ -124,7 +279,14 @@ impl<BuilderAlgorithm: DungeonBuilder> DungeonConfigBuilder<BuilderAlgorithm> {
         self.dungeon_config.room_size.validate()?;
         self.dungeon_config.dungeon_size.validate_room_size(&self.dungeon_config.room_size)?;
 
-        build_algorithm.build(self.dungeon_config)
+        let mut dungeon = build_algorithm.build(self.dungeon_config, rng)?;
+        ensure_connected(&mut dungeon, self.dungeon_config.connectivity, rng)?;
+
+        if self.dungeon_config.should_place_stairs {
+            place_stairs(&mut dungeon)?;
+        }
+
+        Ok(dungeon)
     }
 }
 
@@ -139,7 +301,10 @@ impl Default for DungeonBuildConfig {
                 min_room_size: 5,
                 max_room_size: 10
             },
-            should_place_doors: false
+            should_place_doors: false,
+            seed: None,
+            connectivity: ConnectivityPolicy::Repair,
+            should_place_stairs: false
         }
     }
 }
@@ -0,0 +1,150 @@
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use crate::dungeon::{
+    Dungeon, DungeonBuilder, DungeonBuildConfig, DungeonBuildError,
+    DungeonConfigBuilder
+};
+use crate::stairs::{place_stairs, place_stairs_near};
+
+type ConfigOverride = Box<dyn FnOnce(&mut DungeonBuildConfig)>;
+
+/// Stairs connecting two consecutive levels
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StairLink {
+    pub upper_level: usize,
+    /// position of `TileType::StairsDown` on the upper level
+    pub down: (usize, usize),
+    pub lower_level: usize,
+    /// position of `TileType::StairsUp` on the lower level
+    pub up: (usize, usize),
+}
+
+impl StairLink {
+    /// both stairs are at the same (x, y)
+    pub fn is_aligned(&self) -> bool {
+        self.down == self.up
+    }
+}
+
+/// Levels of one dungeon run, `stairs[i]` connects `levels[i]` with `levels[i + 1]`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DungeonStack {
+    pub levels: Vec<Dungeon>,
+    pub stairs: Vec<StairLink>,
+}
+
+struct LevelConfig {
+    build_algorithm: Box<dyn DungeonBuilder>,
+    config_override: Option<ConfigOverride>,
+}
+
+/// Generates several levels with one rng, every level can use its own algorithm.
+/// Entrance of every level is placed as close as possible to the exit of the level above,
+/// so they are at the same (x, y) whenever that tile is room floor on both levels
+pub struct DungeonStackBuilder {
+    base_config: DungeonBuildConfig,
+    levels: Vec<LevelConfig>,
+    seed: Option<u64>,
+}
+
+impl DungeonStackBuilder {
+    pub fn new() -> Self {
+        Self {
+            base_config: Default::default(),
+            levels: Vec::new(),
+            seed: None,
+        }
+    }
+
+    /// Config shared by all levels, its `seed` is used when stack seed is not set
+    pub fn base_config(mut self, base_config: DungeonBuildConfig) -> Self {
+        self.base_config = base_config;
+        self
+    }
+
+    /// Same seed with the same levels always produces the same stack, takes precedence over base config seed
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn level<BuilderAlgorithm: DungeonBuilder + 'static>(self, build_algorithm: BuilderAlgorithm) -> Self {
+        self.add_level(build_algorithm, None)
+    }
+
+    /// Adds level with changes to the base config, for example bigger rooms deeper down.
+    /// Seed set by the override is ignored, all levels are built with the rng of the stack
+    pub fn level_with<BuilderAlgorithm, Override>(self, build_algorithm: BuilderAlgorithm, config_override: Override) -> Self
+    where
+        BuilderAlgorithm: DungeonBuilder + 'static,
+        Override: FnOnce(&mut DungeonBuildConfig) + 'static,
+    {
+        self.add_level(build_algorithm, Some(Box::new(config_override)))
+    }
+
+    fn add_level<BuilderAlgorithm: DungeonBuilder + 'static>(
+        mut self,
+        build_algorithm: BuilderAlgorithm,
+        config_override: Option<ConfigOverride>,
+    ) -> Self {
+        self.levels.push(LevelConfig { build_algorithm: Box::new(build_algorithm), config_override });
+        self
+    }
+
+    pub fn build(self) -> Result<DungeonStack, DungeonBuildError> {
+        let mut rng = match self.seed.or(self.base_config.seed) {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        self.build_with_rng(&mut rng)
+    }
+
+    /// Builds all levels using provided rng, configured seed is ignored
+    pub fn build_with_rng(self, rng: &mut dyn RngCore) -> Result<DungeonStack, DungeonBuildError> {
+        if self.levels.is_empty() {
+            return Err(DungeonBuildError::NoLevelsProvided);
+        }
+
+        let mut levels: Vec<Dungeon> = Vec::with_capacity(self.levels.len());
+        let mut stairs = Vec::new();
+
+        for (index, level) in self.levels.into_iter().enumerate() {
+            let mut config = self.base_config;
+            if let Some(config_override) = level.config_override {
+                config_override(&mut config);
+            }
+            config.should_place_stairs = false;
+
+            let mut dungeon = DungeonConfigBuilder::new()
+                .build_config(config)
+                .build_algorithm(level.build_algorithm)
+                .build_with_rng(rng)?;
+
+            match levels.last().and_then(|upper| upper.exit) {
+                Some(down) => {
+                    place_stairs_near(&mut dungeon, down)?;
+                    stairs.push(StairLink {
+                        upper_level: index - 1,
+                        down,
+                        lower_level: index,
+                        up: dungeon.entrance.ok_or(DungeonBuildError::NoStairsPlace)?,
+                    });
+                }
+                None => place_stairs(&mut dungeon)?,
+            }
+
+            levels.push(dungeon);
+        }
+
+        Ok(DungeonStack { levels, stairs })
+    }
+}
+
+impl Default for DungeonStackBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
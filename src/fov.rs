@@ -0,0 +1,173 @@
+use crate::dungeon::{Dungeon, TileType};
+use crate::grid::Grid;
+
+/// Tiles which block sight, closed and locked doors are opaque
+pub fn is_opaque(tile: TileType) -> bool {
+    matches!(tile, TileType::Wall | TileType::Door | TileType::LockedDoor)
+}
+
+/// Tiles visible from `origin` within euclidean `radius`, computed with symmetric shadowcasting:
+/// when floor tile `b` is visible from floor tile `a`, `a` is also visible from `b`.
+/// Opaque tiles are visible, but hide everything behind them. `usize::MAX` radius is unlimited
+pub fn field_of_view(map: &Grid<TileType>, origin: (usize, usize), radius: usize) -> Grid<bool> {
+    let mut visible = Grid::new(map.width(), map.height(), false);
+    if !map.in_bounds(origin.0, origin.1) {
+        return visible;
+    }
+
+    // no tile is farther than width + height, so the radius fits into i64 and squares without overflow
+    let radius = radius.min(map.width() + map.height());
+
+    visible[origin] = true;
+    for quadrant in [Quadrant::North, Quadrant::East, Quadrant::South, Quadrant::West] {
+        let mut shadowcast = Shadowcast { map, origin, radius, quadrant, visible: &mut visible };
+        shadowcast.scan(Row { depth: 1, start: Slope::new(-1, 1), end: Slope::new(1, 1) });
+    }
+
+    visible
+}
+
+/// `b` can be seen from `a`, gives the same answer as `field_of_view` from `a`
+pub fn line_of_sight(map: &Grid<TileType>, a: (usize, usize), b: (usize, usize)) -> bool {
+    if !map.in_bounds(a.0, a.1) || !map.in_bounds(b.0, b.1) {
+        return false;
+    }
+
+    let (dx, dy) = (a.0.abs_diff(b.0), a.1.abs_diff(b.1));
+    let radius = ((dx * dx + dy * dy) as f64).sqrt().ceil() as usize;
+    field_of_view(map, a, radius)[b]
+}
+
+impl Dungeon {
+    pub fn field_of_view(&self, origin: (usize, usize), radius: usize) -> Grid<bool> {
+        field_of_view(&self.map, origin, radius)
+    }
+
+    pub fn line_of_sight(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        line_of_sight(&self.map, a, b)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Quadrant {
+    North,
+    East,
+    South,
+    West,
+}
+
+/// rational slope `numerator / denominator`, denominator is always positive
+#[derive(Debug, Copy, Clone)]
+struct Slope {
+    numerator: i64,
+    denominator: i64,
+}
+
+impl Slope {
+    fn new(numerator: i64, denominator: i64) -> Self {
+        Self { numerator, denominator }
+    }
+
+    /// slope to the left edge of the tile
+    fn of_tile(depth: i64, column: i64) -> Self {
+        Self::new(2 * column - 1, 2 * depth)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Row {
+    depth: i64,
+    start: Slope,
+    end: Slope,
+}
+
+impl Row {
+    /// columns from `depth * start` rounded ties up to `depth * end` rounded ties down
+    fn columns(&self) -> std::ops::RangeInclusive<i64> {
+        let min = (2 * self.depth * self.start.numerator + self.start.denominator)
+            .div_euclid(2 * self.start.denominator);
+        let max = -(-(2 * self.depth * self.end.numerator - self.end.denominator))
+            .div_euclid(2 * self.end.denominator);
+        min..=max
+    }
+
+    fn next(&self) -> Row {
+        Row { depth: self.depth + 1, ..*self }
+    }
+
+    /// column center is inside of the row sector, so visibility is symmetric
+    fn is_symmetric(&self, column: i64) -> bool {
+        column * self.start.denominator >= self.depth * self.start.numerator
+            && column * self.end.denominator <= self.depth * self.end.numerator
+    }
+}
+
+struct Shadowcast<'a> {
+    map: &'a Grid<TileType>,
+    origin: (usize, usize),
+    radius: usize,
+    quadrant: Quadrant,
+    visible: &'a mut Grid<bool>,
+}
+
+impl Shadowcast<'_> {
+    fn scan(&mut self, mut row: Row) {
+        if row.depth > self.radius as i64 {
+            return;
+        }
+
+        let mut previous_is_wall = None;
+        for column in row.columns() {
+            let is_wall = self.is_wall(row.depth, column);
+
+            if (is_wall || row.is_symmetric(column)) && self.in_radius(row.depth, column) {
+                self.reveal(row.depth, column);
+            }
+
+            if previous_is_wall == Some(true) && !is_wall {
+                row.start = Slope::of_tile(row.depth, column);
+            }
+
+            if previous_is_wall == Some(false) && is_wall {
+                let mut next = row.next();
+                next.end = Slope::of_tile(row.depth, column);
+                self.scan(next);
+            }
+
+            previous_is_wall = Some(is_wall);
+        }
+
+        if previous_is_wall == Some(false) {
+            self.scan(row.next());
+        }
+    }
+
+    fn position(&self, depth: i64, column: i64) -> Option<(usize, usize)> {
+        let (x, y) = (self.origin.0 as i64, self.origin.1 as i64);
+        let (x, y) = match self.quadrant {
+            Quadrant::North => (x + column, y - depth),
+            Quadrant::South => (x + column, y + depth),
+            Quadrant::East => (x + depth, y + column),
+            Quadrant::West => (x - depth, y + column),
+        };
+
+        let (x, y) = (usize::try_from(x).ok()?, usize::try_from(y).ok()?);
+        self.map.in_bounds(x, y).then_some((x, y))
+    }
+
+    /// tiles outside of the map block sight as walls
+    fn is_wall(&self, depth: i64, column: i64) -> bool {
+        self.position(depth, column).is_none_or(|position| is_opaque(self.map[position]))
+    }
+
+    fn in_radius(&self, depth: i64, column: i64) -> bool {
+        let radius = self.radius as i64;
+        depth * depth + column * column <= radius * radius
+    }
+
+    fn reveal(&mut self, depth: i64, column: i64) {
+        if let Some(position) = self.position(depth, column) {
+            self.visible[position] = true;
+        }
+    }
+}
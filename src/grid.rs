@@ -0,0 +1,201 @@
+use std::ops::{Index, IndexMut};
+
+/// 2d grid stored in one contiguous row-major buffer, indexed by `(x, y)`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "GridParts<T>"))]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    /// Panics when `width * height` overflows `usize`
+    pub fn new(width: usize, height: usize, value: T) -> Self {
+        let len = width.checked_mul(height).expect("Grid size should not overflow usize");
+        Self {
+            width,
+            height,
+            cells: vec![value; len],
+        }
+    }
+
+    /// Converts to nested rows indexed as `[y][x]`
+    pub fn to_nested(&self) -> Vec<Vec<T>> {
+        self.rows().map(|row| row.to_vec()).collect()
+    }
+}
+
+impl<T> Grid<T> {
+    /// Returns `None` when number of cells doesn't match the size or the size overflows `usize`
+    pub fn from_vec(width: usize, height: usize, cells: Vec<T>) -> Option<Self> {
+        if width.checked_mul(height) != Some(cells.len()) {
+            return None;
+        }
+
+        Some(Self { width, height, cells })
+    }
+
+    /// Converts from nested rows indexed as `[y][x]`, returns `None` for ragged rows
+    pub fn from_nested(rows: Vec<Vec<T>>) -> Option<Self> {
+        let height = rows.len();
+        let width = rows.first().map_or(0, |row| row.len());
+
+        if rows.iter().any(|row| row.len() != width) {
+            return None;
+        }
+
+        Self::from_vec(width, height, rows.into_iter().flatten().collect())
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Number of cells in the grid
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn in_bounds(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if !self.in_bounds(x, y) {
+            return None;
+        }
+
+        self.cells.get(y * self.width + x)
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if !self.in_bounds(x, y) {
+            return None;
+        }
+
+        self.cells.get_mut(y * self.width + x)
+    }
+
+    /// Panics when position is out of bounds, same as indexing
+    pub fn set(&mut self, x: usize, y: usize, value: T) {
+        self[(x, y)] = value;
+    }
+
+    /// Orthogonal neighbours which are inside of the grid
+    pub fn neighbors4(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        [(0, -1), (-1, 0), (1, 0), (0, 1)]
+            .into_iter()
+            .filter_map(move |(dx, dy)| self.offset(x, y, dx, dy))
+    }
+
+    /// Orthogonal and diagonal neighbours which are inside of the grid
+    pub fn neighbors8(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)]
+            .into_iter()
+            .filter_map(move |(dx, dy)| self.offset(x, y, dx, dy))
+    }
+
+    fn offset(&self, x: usize, y: usize, dx: isize, dy: isize) -> Option<(usize, usize)> {
+        let nx = x.checked_add_signed(dx)?;
+        let ny = y.checked_add_signed(dy)?;
+        self.in_bounds(nx, ny).then_some((nx, ny))
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[(y * self.width)..((y + 1) * self.width)]
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> &mut [T] {
+        &mut self.cells[(y * self.width)..((y + 1) * self.width)]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // chunks panics on zero size, empty grid has no rows anyway
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        self.cells.iter().skip(x).step_by(self.width.max(1)).take(self.height)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.cells.iter_mut()
+    }
+
+    /// Iterates over `((x, y), cell)` in row-major order
+    pub fn cells(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| ((i % width, i / width), cell))
+    }
+
+    /// Iterates over all positions in row-major order
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
+        assert!(self.in_bounds(x, y), "position ({x}, {y}) is out of grid bounds {}x{}", self.width, self.height);
+        &self.cells[y * self.width + x]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Self::Output {
+        assert!(self.in_bounds(x, y), "position ({x}, {y}) is out of grid bounds {}x{}", self.width, self.height);
+        &mut self.cells[y * self.width + x]
+    }
+}
+
+impl<T> From<Grid<T>> for Vec<Vec<T>> {
+    fn from(grid: Grid<T>) -> Self {
+        let mut rows = Vec::with_capacity(grid.height);
+        let mut cells = grid.cells.into_iter();
+        for _ in 0..grid.height {
+            rows.push(cells.by_ref().take(grid.width).collect());
+        }
+        rows
+    }
+}
+
+/// unchecked grid fields, size is validated when converted to the grid
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct GridParts<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+#[cfg(feature = "serde")]
+impl<T> TryFrom<GridParts<T>> for Grid<T> {
+    type Error = String;
+
+    fn try_from(parts: GridParts<T>) -> Result<Self, Self::Error> {
+        let cells_count = parts.cells.len();
+        Grid::from_vec(parts.width, parts.height, parts.cells).ok_or_else(|| {
+            format!("grid {}x{} can't have {cells_count} cells", parts.width, parts.height)
+        })
+    }
+}
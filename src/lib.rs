@@ -1,9 +1,43 @@
+mod ascii;
 mod binary_partition_builder;
+mod cellular_automata_builder;
+mod connectivity;
+mod corridor;
+mod doors;
+mod drunkard_walk_builder;
+mod dungeon_stack;
+mod locks;
 mod room_placement_builder;
+#[cfg(feature = "image")]
+mod preview;
+mod populator;
+mod registry;
+mod room_graph;
+mod room_tags;
+mod stairs;
+#[cfg(feature = "tiled")]
+mod tiled;
 
 pub mod dungeon;
+pub mod fov;
+pub mod grid;
+pub mod pathfinding;
+pub use ascii::{AsciiParseError, GlyphTable};
 pub use binary_partition_builder::BinaryPartitionBuilder;
+pub use cellular_automata_builder::{CavernHandling, CellularAutomataBuilder};
+pub use corridor::CorridorStyle;
+pub use drunkard_walk_builder::{DrunkardWalkBuilder, SpawnMode, Symmetry};
+pub use dungeon_stack::{DungeonStack, DungeonStackBuilder, StairLink};
+pub use locks::{Lock, LockPlacer};
+pub use populator::{Populator, SpawnPoint, SpawnTable};
+pub use registry::BuilderRegistry;
 pub use room_placement_builder::RoomPlacementBuilder;
+pub use room_graph::{RoomEdge, RoomGraph, RoomPath};
+pub use room_tags::{RoomTag, RoomTagger, TagRule};
+#[cfg(feature = "image")]
+pub use preview::{ImageOptions, TileColors};
+#[cfg(feature = "tiled")]
+pub use tiled::{TileGids, TiledExporter};
 
 #[cfg(test)]
 mod tests {
@@ -13,26 +47,26 @@ mod tests {
     #[test]
     fn test_default_build() {
         let dungeon = DungeonConfigBuilder::new()
-            .build_algorithm(BinaryPartitionBuilder).build().expect("Failed to build dungeon");
-        assert_eq!(dungeon.map.len(), 32);
-        assert_eq!(dungeon.map[0].len(), 32);
+            .build_algorithm(BinaryPartitionBuilder::new()).build().expect("Failed to build dungeon");
+        assert_eq!(dungeon.map.height(), 32);
+        assert_eq!(dungeon.map.width(), 32);
     }
 
     #[test]
     fn test_custom_size() {
         let dungeon = DungeonConfigBuilder::new()
-            .build_algorithm(BinaryPartitionBuilder)
+            .build_algorithm(BinaryPartitionBuilder::new())
             .dungeon_size(DungeonSize { width: 60, height: 40 })
             .build()
             .expect("Failed to build dungeon");
-        assert_eq!(dungeon.map.len(), 40);
-        assert_eq!(dungeon.map[0].len(), 60);
+        assert_eq!(dungeon.map.height(), 40);
+        assert_eq!(dungeon.map.width(), 60);
     }
 
     #[test]
     fn test_min_room_size_greater_than_max_room_size() {
         let result = DungeonConfigBuilder::new()
-            .build_algorithm(BinaryPartitionBuilder)
+            .build_algorithm(BinaryPartitionBuilder::new())
             .room_size(RoomSize { min_room_size: 10, max_room_size: 5 })
             .build();
 
@@ -45,7 +79,7 @@ mod tests {
     #[test]
     fn test_zero_width() {
         let result = DungeonConfigBuilder::new()
-            .build_algorithm(BinaryPartitionBuilder)
+            .build_algorithm(BinaryPartitionBuilder::new())
             .dungeon_size(DungeonSize { width: 0, height: 50 })
             .build();
 
@@ -58,7 +92,7 @@ mod tests {
     #[test]
     fn test_zero_height() {
         let result = DungeonConfigBuilder::new()
-            .build_algorithm(BinaryPartitionBuilder)
+            .build_algorithm(BinaryPartitionBuilder::new())
             .dungeon_size(DungeonSize { width: 50, height: 0 })
             .build();
 
@@ -71,7 +105,7 @@ mod tests {
     #[test]
     fn test_zero_min_room_size() {
         let result = DungeonConfigBuilder::new()
-            .build_algorithm(BinaryPartitionBuilder)
+            .build_algorithm(BinaryPartitionBuilder::new())
             .room_size(RoomSize { min_room_size: 0, max_room_size: 5 })
             .build();
 
@@ -84,7 +118,7 @@ mod tests {
     #[test]
     fn test_zero_max_room_size() {
         let result = DungeonConfigBuilder::new()
-            .build_algorithm(BinaryPartitionBuilder)
+            .build_algorithm(BinaryPartitionBuilder::new())
             .room_size(RoomSize { min_room_size: 1, max_room_size: 0 })
             .build();
 
@@ -97,7 +131,7 @@ mod tests {
     #[test]
     fn test_room_too_large_for_dungeon() {
         let result = DungeonConfigBuilder::new()
-            .build_algorithm(BinaryPartitionBuilder)
+            .build_algorithm(BinaryPartitionBuilder::new())
             .dungeon_size(DungeonSize { width: 10, height: 10 })
             .room_size(RoomSize { min_room_size: 15, max_room_size: 16 })
             .build();
@@ -111,7 +145,7 @@ mod tests {
     #[test]
     fn test_no_rooms_created_partition() {
         let result = DungeonConfigBuilder::new()
-            .build_algorithm(BinaryPartitionBuilder)
+            .build_algorithm(BinaryPartitionBuilder::new())
             .dungeon_size(DungeonSize { width: 1, height: 1 })
             .room_size(RoomSize { min_room_size: 1, max_room_size: 2 })
             .build();
@@ -121,7 +155,7 @@ mod tests {
     #[test]
     fn test_no_rooms_created_room_placement() {
         let result = DungeonConfigBuilder::new()
-            .build_algorithm(RoomPlacementBuilder)
+            .build_algorithm(RoomPlacementBuilder::new())
             .dungeon_size(DungeonSize { width: 5, height: 5 })
             .room_size(RoomSize { min_room_size: 4, max_room_size: 5 })
             .build();
@@ -131,7 +165,7 @@ mod tests {
     #[test]
     fn test_disable_doors() {
         let dungeon = DungeonConfigBuilder::new()
-            .build_algorithm(BinaryPartitionBuilder)
+            .build_algorithm(BinaryPartitionBuilder::new())
             .should_place_doors(false)
             .build()
             .expect("Failed to build dungeon");
@@ -139,18 +173,1832 @@ mod tests {
         let door_count = dungeon
             .map
             .iter()
-            .flatten()
             .filter(|&&tile| tile == TileType::Door)
             .count();
         assert_eq!(door_count, 0);
     }
 
+    fn assert_doors_at_thresholds(dungeon: &Dungeon) -> usize {
+        let map = &dungeon.map;
+        let mut door_count = 0;
+
+        for y in 0..map.height() {
+            for x in 0..map.width() {
+                if map[(x, y)] != TileType::Door {
+                    continue;
+                }
+                door_count += 1;
+
+                let (left, right) = (map[(x - 1, y)], map[(x + 1, y)]);
+                let (up, down) = (map[(x, y - 1)], map[(x, y + 1)]);
+                let vertical = left == TileType::Wall && right == TileType::Wall
+                    && up == TileType::Floor && down == TileType::Floor;
+                let horizontal = up == TileType::Wall && down == TileType::Wall
+                    && left == TileType::Floor && right == TileType::Floor;
+                assert!(vertical || horizontal, "door at ({x}, {y}) is not a chokepoint");
+
+                assert!(dungeon.rooms.iter().all(|room| !room.contains(x, y)));
+                let next_to_room = [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+                    .iter()
+                    .any(|&(nx, ny)| dungeon.rooms.iter().any(|room| room.contains(nx, ny)));
+                assert!(next_to_room, "door at ({x}, {y}) is not next to a room");
+            }
+        }
+
+        door_count
+    }
+
+    #[test]
+    fn test_doors_only_at_thresholds() {
+        let mut door_count = 0;
+        for seed in 0..20 {
+            let partition = DungeonConfigBuilder::new()
+                .build_algorithm(BinaryPartitionBuilder::new())
+                .dungeon_size(DungeonSize { width: 60, height: 40 })
+                .should_place_doors(true)
+                .seed(seed)
+                .build()
+                .expect("Failed to build dungeon");
+            door_count += assert_doors_at_thresholds(&partition);
+
+            let room_placement = DungeonConfigBuilder::new()
+                .build_algorithm(RoomPlacementBuilder::new())
+                .dungeon_size(DungeonSize { width: 60, height: 40 })
+                .should_place_doors(true)
+                .seed(seed)
+                .build()
+                .expect("Failed to build dungeon");
+            door_count += assert_doors_at_thresholds(&room_placement);
+        }
+        assert!(door_count > 0);
+    }
+
+    #[test]
+    fn test_one_door_per_threshold() {
+        let mut rows = vec![vec![TileType::Wall; 9]; 7];
+        for row in &mut rows[1..4] {
+            for tile in &mut row[1..4] {
+                *tile = TileType::Floor;
+            }
+        }
+        // one tile corridor going right from the room and two tiles wide one going down
+        for tile in &mut rows[2][4..8] {
+            *tile = TileType::Floor;
+        }
+        for row in &mut rows[4..6] {
+            row[1] = TileType::Floor;
+            row[2] = TileType::Floor;
+        }
+        let mut map = grid::Grid::from_nested(rows).expect("Rows should have the same length");
+
+        let rooms = vec![dungeon::RoomInfo::new(0, 1, 1, 3, 3)];
+        doors::place_doors(&mut map, &rooms);
+
+        assert_eq!(map[(4, 2)], TileType::Door);
+        assert_eq!(map.iter().filter(|&&tile| tile == TileType::Door).count(), 1);
+    }
+
     #[test]
     fn test_room_placement_algorithm() {
         let dungeon = DungeonConfigBuilder::new()
-            .build_algorithm(RoomPlacementBuilder)
+            .build_algorithm(RoomPlacementBuilder::new())
             .build()
             .expect("Failed to build dungeon");
         assert!(!dungeon.map.is_empty());
     }
+
+    #[test]
+    fn test_same_seed_same_dungeon() {
+        let build = |seed| {
+            DungeonConfigBuilder::new()
+                .build_algorithm(BinaryPartitionBuilder::new())
+                .dungeon_size(DungeonSize { width: 60, height: 40 })
+                .seed(seed)
+                .build()
+                .expect("Failed to build dungeon")
+        };
+        assert_eq!(build(42), build(42));
+
+        let build = |seed| {
+            DungeonConfigBuilder::new()
+                .build_algorithm(RoomPlacementBuilder::new())
+                .dungeon_size(DungeonSize { width: 60, height: 40 })
+                .seed(seed)
+                .build()
+                .expect("Failed to build dungeon")
+        };
+        assert_eq!(build(42), build(42));
+    }
+
+    #[test]
+    fn test_different_seeds_different_dungeons() {
+        let build = |seed| {
+            DungeonConfigBuilder::new()
+                .build_algorithm(RoomPlacementBuilder::new())
+                .dungeon_size(DungeonSize { width: 60, height: 40 })
+                .seed(seed)
+                .build()
+                .expect("Failed to build dungeon")
+        };
+        assert_ne!(build(1), build(2));
+    }
+
+    fn assert_metadata_matches_map(dungeon: &Dungeon) {
+        assert!(!dungeon.rooms.is_empty());
+        for (id, room) in dungeon.rooms.iter().enumerate() {
+            assert_eq!(room.id, id);
+            for y in room.y..(room.y + room.height) {
+                for x in room.x..(room.x + room.width) {
+                    assert_ne!(dungeon.map[(x, y)], TileType::Wall);
+                }
+            }
+        }
+
+        for corridor in &dungeon.corridors {
+            assert_eq!(corridor.start, dungeon.rooms[corridor.from_room].center);
+            assert_eq!(corridor.end, dungeon.rooms[corridor.to_room].center);
+            assert_eq!(corridor.path.first(), Some(&corridor.start));
+            assert_eq!(corridor.path.last(), Some(&corridor.end));
+            for step in corridor.path.windows(2) {
+                let (x1, y1) = step[0];
+                let (x2, y2) = step[1];
+                assert_eq!(x1.abs_diff(x2) + y1.abs_diff(y2), 1);
+            }
+            for &(x, y) in &corridor.path {
+                assert_ne!(dungeon.map[(x, y)], TileType::Wall);
+            }
+        }
+    }
+
+    #[test]
+    fn test_partition_rooms_and_corridors_metadata() {
+        for seed in 0..20 {
+            let dungeon = DungeonConfigBuilder::new()
+                .build_algorithm(BinaryPartitionBuilder::new())
+                .dungeon_size(DungeonSize { width: 60, height: 40 })
+                .seed(seed)
+                .build()
+                .expect("Failed to build dungeon");
+            assert_metadata_matches_map(&dungeon);
+            assert_eq!(dungeon.corridors.len(), dungeon.rooms.len() - 1);
+        }
+    }
+
+    #[test]
+    fn test_room_placement_rooms_and_corridors_metadata() {
+        for seed in 0..20 {
+            let dungeon = DungeonConfigBuilder::new()
+                .build_algorithm(RoomPlacementBuilder::new())
+                .dungeon_size(DungeonSize { width: 60, height: 40 })
+                .seed(seed)
+                .build()
+                .expect("Failed to build dungeon");
+            assert_metadata_matches_map(&dungeon);
+            assert!(dungeon.corridors.len() >= dungeon.rooms.len() - 1);
+        }
+    }
+
+    #[test]
+    fn test_corridor_info_ends() {
+        let corridor = CorridorInfo::new(0, 1, vec![(1, 1), (2, 1), (2, 2)]);
+        assert_eq!(corridor.start, (1, 1));
+        assert_eq!(corridor.end, (2, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Corridor path should contain at least one tile")]
+    fn test_corridor_info_empty_path() {
+        CorridorInfo::new(0, 1, Vec::new());
+    }
+
+    #[test]
+    fn test_cellular_automata_single_cave() {
+        for seed in 0..10 {
+            let dungeon = DungeonConfigBuilder::new()
+                .build_algorithm(CellularAutomataBuilder::new())
+                .dungeon_size(DungeonSize { width: 60, height: 40 })
+                .seed(seed)
+                .build()
+                .expect("Failed to build dungeon");
+
+            assert_eq!(dungeon.map.height(), 40);
+            assert_eq!(dungeon.map.width(), 60);
+            assert_eq!(connectivity::find_regions(&dungeon.map).len(), 1);
+            assert!(dungeon.map.row(0).iter().all(|&tile| tile == TileType::Wall));
+            assert!(dungeon.map.row(39).iter().all(|&tile| tile == TileType::Wall));
+            assert!(dungeon.map.column(0).all(|&tile| tile == TileType::Wall));
+            assert!(dungeon.map.column(59).all(|&tile| tile == TileType::Wall));
+        }
+    }
+
+    #[test]
+    fn test_cellular_automata_keep_largest() {
+        let dungeon = DungeonConfigBuilder::new()
+            .build_algorithm(CellularAutomataBuilder::new().cavern_handling(CavernHandling::KeepLargest))
+            .dungeon_size(DungeonSize { width: 60, height: 40 })
+            .seed(3)
+            .build()
+            .expect("Failed to build dungeon");
+        assert_eq!(connectivity::find_regions(&dungeon.map).len(), 1);
+    }
+
+    #[test]
+    fn test_cellular_automata_no_cave() {
+        let result = DungeonConfigBuilder::new()
+            .build_algorithm(CellularAutomataBuilder::new().wall_density(1.0))
+            .seed(1)
+            .build();
+        assert!(matches!(result, Err(DungeonBuildError::NoRoomsCreated)));
+    }
+
+    fn floor_ratio(dungeon: &Dungeon) -> f64 {
+        let floor = dungeon.map.iter().filter(|&&tile| tile == TileType::Floor).count();
+        floor as f64 / (dungeon.map.height() * dungeon.map.width()) as f64
+    }
+
+    #[test]
+    fn test_drunkard_walk_spawn_modes() {
+        for spawn_mode in [SpawnMode::Center, SpawnMode::RandomFloor, SpawnMode::LastPosition] {
+            let dungeon = DungeonConfigBuilder::new()
+                .build_algorithm(DrunkardWalkBuilder::new().spawn_mode(spawn_mode).floor_percent(0.35))
+                .dungeon_size(DungeonSize { width: 60, height: 40 })
+                .seed(5)
+                .build()
+                .expect("Failed to build dungeon");
+
+            assert!(floor_ratio(&dungeon) >= 0.35);
+            assert_eq!(connectivity::find_regions(&dungeon.map).len(), 1);
+            assert!(dungeon.map.row(0).iter().all(|&tile| tile == TileType::Wall));
+        }
+    }
+
+    #[test]
+    fn test_drunkard_walk_symmetry() {
+        let dungeon = DungeonConfigBuilder::new()
+            .build_algorithm(DrunkardWalkBuilder::new().symmetry(Symmetry::Both).spawn_mode(SpawnMode::RandomFloor))
+            .dungeon_size(DungeonSize { width: 40, height: 30 })
+            .seed(11)
+            .build()
+            .expect("Failed to build dungeon");
+
+        for y in 0..30 {
+            for x in 0..40 {
+                assert_eq!(dungeon.map[(x, y)], dungeon.map[(39 - x, y)]);
+                assert_eq!(dungeon.map[(x, y)], dungeon.map[(x, 29 - y)]);
+            }
+        }
+        assert_eq!(connectivity::find_regions(&dungeon.map).len(), 1);
+    }
+
+    #[test]
+    fn test_drunkard_walk_step_limit() {
+        let dungeon = DungeonConfigBuilder::new()
+            .build_algorithm(DrunkardWalkBuilder::new().max_steps(10).max_walkers(1).floor_percent(1.0))
+            .seed(2)
+            .build()
+            .expect("Failed to build dungeon");
+        let floor = dungeon.map.iter().filter(|&&tile| tile == TileType::Floor).count();
+        assert!(floor > 0 && floor <= 10);
+    }
+
+    struct FixedMapBuilder(grid::Grid<TileType>, Vec<RoomInfo>);
+
+    impl DungeonBuilder for FixedMapBuilder {
+        fn build(&self, _: DungeonBuildConfig, _: &mut dyn rand::RngCore) -> Result<Dungeon, DungeonBuildError> {
+            Ok(Dungeon { map: self.0.clone(), rooms: self.1.clone(), corridors: Vec::new(), entrance: None, exit: None })
+        }
+    }
+
+    fn two_caves_map() -> grid::Grid<TileType> {
+        let mut map = grid::Grid::new(12, 8, TileType::Wall);
+        for y in 1..4 {
+            for x in 1..4 {
+                map[(x, y)] = TileType::Floor;
+            }
+        }
+        for y in 5..7 {
+            for x in 8..11 {
+                map[(x, y)] = TileType::Floor;
+            }
+        }
+        map
+    }
+
+    #[test]
+    fn test_disconnected_map_fails() {
+        let result = DungeonConfigBuilder::new()
+            .build_algorithm(FixedMapBuilder(two_caves_map(), Vec::new()))
+            .connectivity(ConnectivityPolicy::Fail)
+            .build();
+
+        match result {
+            Err(DungeonBuildError::Disconnected(sizes)) => assert_eq!(sizes, vec![9, 6]),
+            _ => panic!("Expected disconnected error"),
+        }
+    }
+
+    #[test]
+    fn test_disconnected_map_repaired() {
+        let dungeon = DungeonConfigBuilder::new()
+            .build_algorithm(FixedMapBuilder(two_caves_map(), Vec::new()))
+            .connectivity(ConnectivityPolicy::Repair)
+            .seed(1)
+            .build()
+            .expect("Failed to build dungeon");
+        assert_eq!(connectivity::find_regions(&dungeon.map).len(), 1);
+    }
+
+    #[test]
+    fn test_all_builders_connected() {
+        for seed in 0..10 {
+            let dungeons = [
+                DungeonConfigBuilder::new()
+                    .build_algorithm(BinaryPartitionBuilder::new())
+                    .dungeon_size(DungeonSize { width: 60, height: 40 })
+                    .should_place_doors(true)
+                    .connectivity(ConnectivityPolicy::Fail)
+                    .seed(seed)
+                    .build(),
+                DungeonConfigBuilder::new()
+                    .build_algorithm(RoomPlacementBuilder::new())
+                    .dungeon_size(DungeonSize { width: 60, height: 40 })
+                    .should_place_doors(true)
+                    .connectivity(ConnectivityPolicy::Fail)
+                    .seed(seed)
+                    .build(),
+            ];
+            for dungeon in dungeons {
+                let dungeon = dungeon.expect("Generated dungeon is not connected");
+                assert_eq!(connectivity::find_regions(&dungeon.map).len(), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_drunkard_walk_nothing_carved() {
+        let builders = [
+            DrunkardWalkBuilder::new().max_steps(0).spawn_mode(SpawnMode::RandomFloor),
+            DrunkardWalkBuilder::new().floor_percent(0.0),
+            DrunkardWalkBuilder::new().max_walkers(0),
+        ];
+
+        for builder in builders {
+            let result = DungeonConfigBuilder::new().build_algorithm(builder).seed(4).build();
+            assert!(matches!(result, Err(DungeonBuildError::NoRoomsCreated)), "{builder:?}");
+        }
+    }
+
+    #[test]
+    fn test_drunkard_walk_mirrored_tiles_are_spawn_points() {
+        let builder = DrunkardWalkBuilder::new().symmetry(Symmetry::Both);
+        let mut map = grid::Grid::new(10, 8, TileType::Wall);
+
+        let mut carved = builder.carve(&mut map, 2, 1);
+        carved.sort();
+        assert_eq!(carved, vec![(2, 1), (2, 6), (7, 1), (7, 6)]);
+        assert!(builder.carve(&mut map, 7, 6).is_empty(), "already carved tiles are not returned again");
+
+        // short walkers spawned on any floor tile, mirrored ones included, still reach the target
+        let dungeon = DungeonConfigBuilder::new()
+            .build_algorithm(
+                DrunkardWalkBuilder::new()
+                    .symmetry(Symmetry::Horizontal)
+                    .spawn_mode(SpawnMode::RandomFloor)
+                    .max_steps(3)
+                    .floor_percent(0.3),
+            )
+            .dungeon_size(DungeonSize { width: 40, height: 30 })
+            .seed(8)
+            .build()
+            .expect("Failed to build dungeon");
+        assert!(floor_ratio(&dungeon) >= 0.3);
+        for ((x, y), &tile) in dungeon.map.cells() {
+            assert_eq!(tile, dungeon.map[(39 - x, y)]);
+        }
+    }
+
+    #[test]
+    fn test_grid_indexing_and_neighbours() {
+        let mut grid = grid::Grid::new(4, 3, 0);
+        grid.set(3, 1, 7);
+        grid[(0, 2)] = 5;
+
+        assert_eq!(grid.get(3, 1), Some(&7));
+        assert_eq!(grid[(0, 2)], 5);
+        assert_eq!(grid.get(4, 0), None);
+        assert!(!grid.in_bounds(0, 3));
+        assert_eq!(grid.row(1), &[0, 0, 0, 7]);
+        assert_eq!(grid.column(0).copied().collect::<Vec<_>>(), vec![0, 0, 5]);
+        assert_eq!(grid.rows().count(), 3);
+
+        assert_eq!(grid.neighbors4(0, 0).collect::<Vec<_>>(), vec![(1, 0), (0, 1)]);
+        assert_eq!(grid.neighbors8(0, 0).count(), 3);
+        assert_eq!(grid.neighbors8(1, 1).count(), 8);
+        assert_eq!(grid.neighbors4(3, 2).collect::<Vec<_>>(), vec![(3, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn test_grid_nested_conversion() {
+        let nested = vec![vec![1, 2, 3], vec![4, 5, 6]];
+        let grid = grid::Grid::from_nested(nested.clone()).expect("Rows should have the same length");
+
+        assert_eq!(grid.width(), 3);
+        assert_eq!(grid.height(), 2);
+        assert_eq!(grid[(2, 0)], 3);
+        assert_eq!(grid[(0, 1)], 4);
+        assert_eq!(grid.to_nested(), nested);
+        assert_eq!(Vec::<Vec<i32>>::from(grid), nested);
+
+        assert!(grid::Grid::from_nested(vec![vec![1, 2], vec![3]]).is_none());
+        assert!(grid::Grid::from_vec(2, 2, vec![1, 2, 3]).is_none());
+        assert!(grid::Grid::<i32>::from_vec(1 << (usize::BITS - 1), 2, Vec::new()).is_none());
+        assert!(grid::Grid::<i32>::from_vec(usize::MAX, 2, Vec::new()).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Grid size should not overflow usize")]
+    fn test_grid_size_overflow() {
+        grid::Grid::new(usize::MAX, 2, 0u8);
+    }
+
+    fn assert_stairs_in_different_rooms(dungeon: &Dungeon) {
+        let entrance = dungeon.entrance.expect("Entrance should be placed");
+        let exit = dungeon.exit.expect("Exit should be placed");
+
+        assert_eq!(dungeon.map[entrance], TileType::StairsUp);
+        assert_eq!(dungeon.map[exit], TileType::StairsDown);
+        assert_eq!(dungeon.map.iter().filter(|&&tile| tile == TileType::StairsUp).count(), 1);
+        assert_eq!(dungeon.map.iter().filter(|&&tile| tile == TileType::StairsDown).count(), 1);
+
+        let entrance_room = dungeon.rooms.iter().position(|room| room.contains(entrance.0, entrance.1));
+        let exit_room = dungeon.rooms.iter().position(|room| room.contains(exit.0, exit.1));
+        assert!(entrance_room.is_some() && exit_room.is_some());
+        assert_ne!(entrance_room, exit_room);
+    }
+
+    #[test]
+    fn test_stairs_placement() {
+        for seed in 0..10 {
+            let partition = DungeonConfigBuilder::new()
+                .build_algorithm(BinaryPartitionBuilder::new())
+                .dungeon_size(DungeonSize { width: 60, height: 40 })
+                .should_place_doors(true)
+                .should_place_stairs(true)
+                .seed(seed)
+                .build()
+                .expect("Failed to build dungeon");
+            assert_stairs_in_different_rooms(&partition);
+
+            let room_placement = DungeonConfigBuilder::new()
+                .build_algorithm(RoomPlacementBuilder::new())
+                .dungeon_size(DungeonSize { width: 60, height: 40 })
+                .should_place_doors(true)
+                .should_place_stairs(true)
+                .seed(seed)
+                .build()
+                .expect("Failed to build dungeon");
+            assert_stairs_in_different_rooms(&room_placement);
+        }
+    }
+
+    #[test]
+    fn test_stairs_in_farthest_rooms() {
+        fn carve_rooms(map: &mut grid::Grid<TileType>, rooms: &[RoomInfo]) {
+            for room in rooms {
+                for y in room.y..(room.y + room.height) {
+                    for x in room.x..(room.x + room.width) {
+                        map[(x, y)] = TileType::Floor;
+                    }
+                }
+            }
+        }
+
+        // three rooms in a row connected with one corridor, middle room is the closest to both
+        let mut map = grid::Grid::new(20, 5, TileType::Wall);
+        for x in 1..19 {
+            map[(x, 2)] = TileType::Floor;
+        }
+        let rooms = vec![
+            RoomInfo::new(0, 8, 1, 3, 3),
+            RoomInfo::new(1, 1, 1, 3, 3),
+            RoomInfo::new(2, 15, 1, 3, 3),
+        ];
+        carve_rooms(&mut map, &rooms);
+
+        let dungeon = DungeonConfigBuilder::new()
+            .build_algorithm(FixedMapBuilder(map, rooms))
+            .should_place_stairs(true)
+            .build()
+            .expect("Failed to build dungeon");
+        assert_eq!(dungeon.entrance, Some((2, 2)));
+        assert_eq!(dungeon.exit, Some((16, 2)));
+
+        // big room with branches from three corners, room 3 is the farthest from its center,
+        // but rooms 1 and 2 on opposite corners are farther from each other than from room 3
+        let mut map = grid::Grid::new(18, 12, TileType::Wall);
+        for corridor in [(4, 3), (12, 9), (12, 3), (13, 3)] {
+            map[corridor] = TileType::Floor;
+        }
+        let rooms = vec![
+            RoomInfo::new(0, 5, 3, 7, 7),
+            RoomInfo::new(1, 1, 2, 3, 3),
+            RoomInfo::new(2, 13, 8, 3, 3),
+            RoomInfo::new(3, 14, 2, 3, 3),
+        ];
+        carve_rooms(&mut map, &rooms);
+
+        let dungeon = DungeonConfigBuilder::new()
+            .build_algorithm(FixedMapBuilder(map, rooms))
+            .should_place_stairs(true)
+            .build()
+            .expect("Failed to build dungeon");
+        assert_eq!(dungeon.entrance, Some((2, 3)));
+        assert_eq!(dungeon.exit, Some((14, 9)));
+    }
+
+    #[test]
+    fn test_stairs_in_caves() {
+        let dungeon = DungeonConfigBuilder::new()
+            .build_algorithm(CellularAutomataBuilder::new())
+            .dungeon_size(DungeonSize { width: 60, height: 40 })
+            .should_place_stairs(true)
+            .seed(4)
+            .build()
+            .expect("Failed to build dungeon");
+        let entrance = dungeon.entrance.expect("Entrance should be placed");
+        let exit = dungeon.exit.expect("Exit should be placed");
+        assert_ne!(entrance, exit);
+        assert_eq!(dungeon.map[entrance], TileType::StairsUp);
+        assert_eq!(dungeon.map[exit], TileType::StairsDown);
+    }
+
+    fn build_stack(seed: u64) -> DungeonStack {
+        DungeonStackBuilder::new()
+            .base_config(DungeonBuildConfig {
+                dungeon_size: DungeonSize { width: 50, height: 40 },
+                ..Default::default()
+            })
+            .level(BinaryPartitionBuilder::new())
+            .level(RoomPlacementBuilder::new())
+            .level_with(BinaryPartitionBuilder::new(), |config| {
+                config.dungeon_size = DungeonSize { width: 70, height: 50 };
+                config.room_size = RoomSize { min_room_size: 8, max_room_size: 12 };
+            })
+            .seed(seed)
+            .build()
+            .expect("Failed to build dungeon stack")
+    }
+
+    #[test]
+    fn test_dungeon_stack_linked_stairs() {
+        let stack = build_stack(9);
+
+        assert_eq!(stack.levels.len(), 3);
+        assert_eq!(stack.stairs.len(), 2);
+        assert_eq!(stack.levels[2].map.width(), 70);
+        assert!(stack.levels[2].rooms.iter().all(|room| room.width >= 8 && room.height >= 8));
+
+        for (i, link) in stack.stairs.iter().enumerate() {
+            let upper = &stack.levels[link.upper_level];
+            let lower = &stack.levels[link.lower_level];
+            assert_eq!((link.upper_level, link.lower_level), (i, i + 1));
+            assert_eq!(upper.exit, Some(link.down));
+            assert_eq!(lower.entrance, Some(link.up));
+            assert_eq!(upper.map[link.down], TileType::StairsDown);
+            assert_eq!(lower.map[link.up], TileType::StairsUp);
+
+            // up stairs are on the closest room floor tile to the down stairs of the level above
+            let distance = |(x, y): (usize, usize)| x.abs_diff(link.down.0) + y.abs_diff(link.down.1);
+            assert!(lower.map
+                .cells()
+                .filter(|&(_, &tile)| tile == TileType::Floor)
+                .filter(|&((x, y), _)| lower.rooms.iter().any(|room| room.contains(x, y)))
+                .all(|(position, _)| distance(link.up) <= distance(position)));
+            assert_eq!(link.is_aligned(), distance(link.up) == 0);
+        }
+    }
+
+    #[test]
+    fn test_dungeon_stack_entrances_inside_rooms() {
+        for seed in 0..40 {
+            let stack = DungeonStackBuilder::new()
+                .base_config(DungeonBuildConfig {
+                    dungeon_size: DungeonSize { width: 60, height: 40 },
+                    should_place_doors: true,
+                    ..Default::default()
+                })
+                .level(RoomPlacementBuilder::new())
+                .level(BinaryPartitionBuilder::new())
+                .level(RoomPlacementBuilder::new())
+                .seed(seed)
+                .build()
+                .expect("Failed to build dungeon stack");
+
+            for link in &stack.stairs {
+                let lower = &stack.levels[link.lower_level];
+                assert!(
+                    lower.rooms.iter().any(|room| room.contains(link.up.0, link.up.1)),
+                    "seed {seed}: entrance {:?} of level {} is outside of rooms", link.up, link.lower_level,
+                );
+                assert_eq!(lower.map[link.up], TileType::StairsUp);
+            }
+        }
+    }
+
+    #[test]
+    fn test_dungeon_stack_same_seed() {
+        assert_eq!(build_stack(3), build_stack(3));
+
+        let build_seeded = |seed| DungeonStackBuilder::new()
+            .base_config(DungeonBuildConfig { seed, ..Default::default() })
+            .level(BinaryPartitionBuilder::new())
+            .level(RoomPlacementBuilder::new())
+            .build()
+            .expect("Failed to build dungeon stack");
+        assert_eq!(build_seeded(Some(5)), build_seeded(Some(5)));
+        assert_eq!(
+            build_seeded(Some(5)),
+            DungeonStackBuilder::new()
+                .seed(5)
+                .level(BinaryPartitionBuilder::new())
+                .level(RoomPlacementBuilder::new())
+                .build()
+                .expect("Failed to build dungeon stack"),
+        );
+    }
+
+    #[test]
+    fn test_dungeon_stack_without_levels() {
+        let result = DungeonStackBuilder::new().build();
+        assert!(matches!(result, Err(DungeonBuildError::NoLevelsProvided)));
+    }
+
+    #[test]
+    fn test_ascii_render() {
+        let mut map = grid::Grid::new(5, 3, TileType::Wall);
+        map[(1, 1)] = TileType::StairsUp;
+        map[(2, 1)] = TileType::Door;
+        map[(3, 1)] = TileType::StairsDown;
+        let dungeon = Dungeon { map, rooms: Vec::new(), corridors: Vec::new(), entrance: None, exit: None };
+
+        assert_eq!(dungeon.to_string(), "#####\n#<+>#\n#####\n");
+
+        let glyphs = GlyphTable { wall: 'W', door: 'D', ..Default::default() };
+        assert_eq!(dungeon.to_ascii(&glyphs), "WWWWW\nW<D>W\nWWWWW\n");
+    }
+
+    #[test]
+    fn test_ascii_round_trip() {
+        let dungeon = DungeonConfigBuilder::new()
+            .build_algorithm(RoomPlacementBuilder::new())
+            .should_place_doors(true)
+            .should_place_stairs(true)
+            .seed(8)
+            .build()
+            .expect("Failed to build dungeon");
+
+        let parsed = Dungeon::from_ascii(&dungeon.to_string()).expect("Failed to parse dungeon");
+        assert_eq!(parsed.map, dungeon.map);
+        assert_eq!(parsed.entrance, dungeon.entrance);
+        assert_eq!(parsed.exit, dungeon.exit);
+
+        let glyphs = GlyphTable { wall: 'x', floor: ' ', ..Default::default() };
+        let parsed = Dungeon::from_ascii_with(&dungeon.to_ascii(&glyphs), &glyphs).expect("Failed to parse dungeon");
+        assert_eq!(parsed.map, dungeon.map);
+    }
+
+    #[test]
+    fn test_ascii_parse_errors() {
+        assert!(matches!(Dungeon::from_ascii(""), Err(AsciiParseError::Empty)));
+        assert!(matches!(
+            Dungeon::from_ascii("###\n#?#\n###"),
+            Err(AsciiParseError::UnknownGlyph('?', 1, 1))
+        ));
+        assert!(matches!(
+            Dungeon::from_ascii("####\n#..#\n###"),
+            Err(AsciiParseError::RaggedRow(2, 4, 3))
+        ));
+
+        let dungeon = Dungeon::from_ascii("#####\r\n#.<.#\r\n#####").expect("Failed to parse dungeon");
+        assert_eq!(dungeon.map.width(), 5);
+        assert_eq!(dungeon.entrance, Some((2, 1)));
+        assert_eq!(dungeon.exit, None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let dungeon = DungeonConfigBuilder::new()
+            .build_algorithm(BinaryPartitionBuilder::new())
+            .should_place_doors(true)
+            .should_place_stairs(true)
+            .seed(12)
+            .build()
+            .expect("Failed to build dungeon");
+        let json = serde_json::to_string(&dungeon).expect("Failed to serialize dungeon");
+        assert_eq!(serde_json::from_str::<Dungeon>(&json).expect("Failed to deserialize dungeon"), dungeon);
+
+        let tiles = [
+            TileType::Wall,
+            TileType::Floor,
+            TileType::Door,
+            TileType::StairsUp,
+            TileType::StairsDown,
+            TileType::LockedDoor,
+        ];
+        for tile in tiles {
+            let json = serde_json::to_string(&tile).expect("Failed to serialize tile");
+            assert_eq!(serde_json::from_str::<TileType>(&json).expect("Failed to deserialize tile"), tile);
+        }
+
+        let size = DungeonSize { width: 40, height: 30 };
+        let json = serde_json::to_string(&size).expect("Failed to serialize size");
+        let parsed: DungeonSize = serde_json::from_str(&json).expect("Failed to deserialize size");
+        assert_eq!((parsed.width, parsed.height), (40, 30));
+
+        let room_size = RoomSize { min_room_size: 3, max_room_size: 7 };
+        let json = serde_json::to_string(&room_size).expect("Failed to serialize room size");
+        let parsed: RoomSize = serde_json::from_str(&json).expect("Failed to deserialize room size");
+        assert_eq!((parsed.min_room_size, parsed.max_room_size), (3, 7));
+
+        let config = DungeonBuildConfig {
+            dungeon_size: size,
+            room_size,
+            should_place_doors: true,
+            seed: Some(5),
+            connectivity: ConnectivityPolicy::Fail,
+            should_place_stairs: true,
+        };
+        let json = serde_json::to_string(&config).expect("Failed to serialize config");
+        let parsed: DungeonBuildConfig = serde_json::from_str(&json).expect("Failed to deserialize config");
+        assert_eq!(format!("{parsed:?}"), format!("{config:?}"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_partial_config_and_invalid_grid() {
+        let config: DungeonBuildConfig = serde_json::from_str(r#"{"seed": 3, "should_place_doors": true}"#)
+            .expect("Failed to deserialize config");
+        assert_eq!(config.seed, Some(3));
+        assert!(config.should_place_doors);
+        assert_eq!(config.dungeon_size.width, 32);
+
+        let result = serde_json::from_str::<grid::Grid<TileType>>(r#"{"width": 2, "height": 2, "cells": ["Wall"]}"#);
+        assert!(result.is_err());
+
+        // width * height overflows usize and must not wrap around to the number of cells
+        for width in [usize::MAX, 1 << (usize::BITS - 1)] {
+            let json = format!(r#"{{"width": {width}, "height": 2, "cells": []}}"#);
+            assert!(serde_json::from_str::<grid::Grid<TileType>>(&json).is_err());
+        }
+    }
+
+    #[cfg(feature = "tiled")]
+    fn tiled_test_dungeon() -> Dungeon {
+        DungeonConfigBuilder::new()
+            .build_algorithm(RoomPlacementBuilder::new())
+            .should_place_doors(true)
+            .should_place_stairs(true)
+            .seed(21)
+            .build()
+            .expect("Failed to build dungeon")
+    }
+
+    #[cfg(feature = "tiled")]
+    #[test]
+    fn test_tiled_json_export() {
+        let dungeon = tiled_test_dungeon();
+        let gids = TileGids { wall: 10, floor: 11, door: 12, stairs_up: 13, stairs_down: 14, locked_door: 15 };
+        let tmj = TiledExporter::new().tile_gids(gids).tile_size(32, 32).to_tmj(&dungeon);
+        let map: serde_json::Value = serde_json::from_str(&tmj).expect("Export should be valid json");
+
+        assert_eq!(map["width"], 32);
+        assert_eq!(map["tilewidth"], 32);
+        let data = map["layers"][0]["data"].as_array().expect("Tile layer should have data");
+        assert_eq!(data.len(), dungeon.map.len());
+        for ((x, y), &tile) in dungeon.map.cells() {
+            assert_eq!(data[y * dungeon.map.width() + x], gids.gid(tile));
+        }
+
+        let objects = map["layers"][1]["objects"].as_array().expect("Object layer should have objects");
+        assert_eq!(objects.len(), dungeon.rooms.len() + dungeon.corridors.len() + 2);
+        let room = &dungeon.rooms[0];
+        assert_eq!(objects[0]["x"], room.x * 32);
+        assert_eq!(objects[0]["width"], room.width * 32);
+        assert_eq!(objects[0]["properties"][0]["value"], 0);
+
+        let corridor = &objects[dungeon.rooms.len()];
+        let polyline = corridor["polyline"].as_array().expect("Corridor should be a polyline");
+        assert!(polyline.len() >= 2 && polyline.len() <= 3);
+        assert_eq!(polyline[0]["x"], 0);
+    }
+
+    #[cfg(feature = "tiled")]
+    #[test]
+    fn test_tiled_xml_export() {
+        let dungeon = tiled_test_dungeon();
+        let tmx = TiledExporter::new().tileset_source("a&b.tsx").to_tmx(&dungeon);
+
+        assert!(tmx.starts_with("<?xml"));
+        assert!(tmx.contains("source=\"a&amp;b.tsx\""));
+        assert_eq!(tmx.matches("<object ").count(), dungeon.rooms.len() + dungeon.corridors.len() + 2);
+        assert_eq!(tmx.matches("<polyline").count(), dungeon.corridors.len());
+
+        let csv = tmx.split("<data encoding=\"csv\">\n").nth(1)
+            .and_then(|data| data.split("\n  </data>").next())
+            .expect("Tile layer should have csv data");
+        assert_eq!(csv.lines().count(), dungeon.map.height());
+        let first_row: Vec<u32> = csv.lines().next().unwrap_or_default()
+            .trim_end_matches(',')
+            .split(',')
+            .map(|gid| gid.parse().expect("Gid should be a number"))
+            .collect();
+        assert_eq!(first_row.len(), dungeon.map.width());
+        assert!(first_row.iter().all(|&gid| gid == TileGids::default().wall));
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_image_tiles() {
+        let dungeon = Dungeon::from_ascii("#####\n#<.>#\n#.+.#\n#####\n").expect("Failed to parse map");
+        let options = ImageOptions { tile_size: 4, ..Default::default() };
+        let image = dungeon.to_image(&options);
+
+        assert_eq!(image.dimensions(), (20, 16));
+        for ((x, y), &tile) in dungeon.map.cells() {
+            let expected = options.colors.color(tile);
+            assert_eq!(*image.get_pixel(x as u32 * 4, y as u32 * 4), expected);
+            assert_eq!(*image.get_pixel(x as u32 * 4 + 3, y as u32 * 4 + 3), expected);
+        }
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_image_overlays() {
+        let dungeon = DungeonConfigBuilder::new()
+            .build_algorithm(RoomPlacementBuilder::new())
+            .seed(13)
+            .build()
+            .expect("Failed to build dungeon");
+        let options = ImageOptions { tile_size: 8, ..ImageOptions::with_overlays() };
+        let image = dungeon.to_image(&options);
+        let plain = dungeon.to_image(&ImageOptions { tile_size: 8, ..Default::default() });
+
+        for room in &dungeon.rooms {
+            let (left, top) = (room.x as u32 * 8, room.y as u32 * 8);
+            assert_eq!(Some(*image.get_pixel(left, top)), options.room_outlines);
+            assert_eq!(Some(*image.get_pixel(left + room.width as u32 * 8 - 1, top + 4)), options.room_outlines);
+            assert_eq!(*plain.get_pixel(left, top), options.colors.floor);
+        }
+
+        // top left pixel of the first digit of room 0
+        let first = &dungeon.rooms[0];
+        assert_eq!(Some(*image.get_pixel(first.x as u32 * 8 + 4, first.y as u32 * 8 + 4)), options.room_ids);
+
+        let corridor = dungeon.corridors
+            .iter()
+            .find_map(|corridor| corridor.path.iter().find(|&&(x, y)| !dungeon.rooms.iter().any(|room| room.contains(x, y))))
+            .expect("Some corridor should leave rooms");
+        assert_eq!(Some(*image.get_pixel(corridor.0 as u32 * 8 + 4, corridor.1 as u32 * 8 + 4)), options.corridor_paths);
+    }
+
+    #[test]
+    fn test_registry_builds_every_algorithm() {
+        let registry = BuilderRegistry::new();
+        let names: Vec<&str> = registry.names().collect();
+        assert_eq!(names, vec!["bsp", "cellular_automata", "drunkard_walk", "room_placement"]);
+        assert!(BuilderRegistry::default().names().eq(registry.names()));
+        assert_eq!(BuilderRegistry::empty().names().count(), 0);
+
+        for name in names {
+            let dungeon = DungeonConfigBuilder::new()
+                .build_algorithm(registry.create(name).expect("Algorithm should be registered"))
+                .seed(15)
+                .build();
+            assert!(dungeon.is_ok(), "{name} failed: {dungeon:?}");
+        }
+
+        match registry.create("maze") {
+            Err(DungeonBuildError::UnknownBuildAlgorithm(name)) => assert_eq!(name, "maze"),
+            _ => panic!("Expected unknown algorithm error"),
+        }
+    }
+
+    #[test]
+    fn test_boxed_builders_match_concrete_builders() {
+        fn build(builder: impl DungeonBuilder) -> Dungeon {
+            DungeonConfigBuilder::new()
+                .build_algorithm(builder)
+                .seed(4)
+                .build()
+                .expect("Failed to build dungeon")
+        }
+        let build_boxed = |builder: Box<dyn DungeonBuilder>| build(builder);
+
+        assert_eq!(
+            build_boxed(Box::new(BinaryPartitionBuilder::new().room_padding(1))),
+            build(BinaryPartitionBuilder::new().room_padding(1)),
+        );
+        assert_eq!(
+            build_boxed(Box::new(RoomPlacementBuilder::new().min_spacing(1))),
+            build(RoomPlacementBuilder::new().min_spacing(1)),
+        );
+        assert_eq!(
+            build_boxed(Box::new(CellularAutomataBuilder::new().smoothing_passes(2))),
+            build(CellularAutomataBuilder::new().smoothing_passes(2)),
+        );
+        assert_eq!(
+            build_boxed(Box::new(DrunkardWalkBuilder::new().floor_percent(0.3))),
+            build(DrunkardWalkBuilder::new().floor_percent(0.3)),
+        );
+
+        let mut registry = BuilderRegistry::empty();
+        registry.register("rooms", || Box::new(RoomPlacementBuilder::new()));
+        let stack = DungeonStackBuilder::new()
+            .seed(4)
+            .level(registry.create("rooms").expect("Algorithm should be registered"))
+            .level(BinaryPartitionBuilder::new())
+            .build()
+            .expect("Failed to build stack");
+        assert_eq!(stack.levels.len(), 2);
+    }
+
+    #[test]
+    fn test_bsp_depth_and_stop_probability() {
+        let build = |builder: BinaryPartitionBuilder, seed| DungeonConfigBuilder::new()
+            .dungeon_size(DungeonSize { width: 60, height: 60 })
+            .room_size(RoomSize { min_room_size: 4, max_room_size: 8 })
+            .build_algorithm(builder)
+            .seed(seed)
+            .build()
+            .expect("Failed to build dungeon");
+
+        for seed in 0..10 {
+            assert_eq!(build(BinaryPartitionBuilder::new().max_depth(0), seed).rooms.len(), 1);
+            assert!(build(BinaryPartitionBuilder::new().max_depth(2), seed).rooms.len() <= 4);
+        }
+
+        let single_room = DungeonConfigBuilder::new()
+            .dungeon_size(DungeonSize { width: 20, height: 20 })
+            .room_size(RoomSize { min_room_size: 4, max_room_size: 20 })
+            .build_algorithm(BinaryPartitionBuilder::new().stop_probability(1.0))
+            .seed(3)
+            .build()
+            .expect("Failed to build dungeon");
+        assert_eq!(single_room.rooms.len(), 1);
+    }
+
+    #[test]
+    fn test_bsp_split_ratio_and_padding() {
+        for seed in 0..10 {
+            let dungeon = DungeonConfigBuilder::new()
+                .dungeon_size(DungeonSize { width: 40, height: 30 })
+                .room_size(RoomSize { min_room_size: 4, max_room_size: 20 })
+                .build_algorithm(BinaryPartitionBuilder::new().max_depth(1).split_ratio(0.5, 0.5).room_padding(2))
+                .seed(seed)
+                .build()
+                .expect("Failed to build dungeon");
+
+            assert_eq!(dungeon.rooms.len(), 2);
+            let (left, right) = (&dungeon.rooms[0], &dungeon.rooms[1]);
+            assert!(left.x >= 2 && left.y >= 2 && left.y + left.height <= 28);
+            assert!(left.x + left.width <= 18, "room {left:?} is inside of padding");
+            assert!(right.x >= 22 && right.x + right.width <= 38);
+        }
+    }
+
+    #[test]
+    fn test_bsp_corridor_style() {
+        let build = |builder: BinaryPartitionBuilder| DungeonConfigBuilder::new()
+            .dungeon_size(DungeonSize { width: 50, height: 40 })
+            .build_algorithm(builder)
+            .seed(8)
+            .build()
+            .expect("Failed to build dungeon");
+        let floor = |dungeon: &Dungeon| dungeon.map.iter().filter(|&&tile| tile == TileType::Floor).count();
+
+        let narrow = build(BinaryPartitionBuilder::new());
+        let wide = build(BinaryPartitionBuilder::new().corridor_width(3));
+        assert_eq!(narrow.rooms, wide.rooms);
+        assert!(floor(&wide) > floor(&narrow));
+        // wide brush never carves the border of the map
+        for ((x, y), &tile) in wide.map.cells() {
+            let is_border = x == 0 || y == 0 || x == wide.map.width() - 1 || y == wide.map.height() - 1;
+            if is_border {
+                assert_eq!(tile, narrow.map[(x, y)]);
+            }
+        }
+
+        let z_shaped = build(BinaryPartitionBuilder::new().corridor_style(CorridorStyle::ZShaped));
+        assert_eq!(narrow.rooms, z_shaped.rooms);
+        for corridor in &z_shaped.corridors {
+            let turns = corridor.path
+                .windows(3)
+                .filter(|step| step[0].0 != step[2].0 && step[0].1 != step[2].1)
+                .count();
+            assert!(turns <= 2, "corridor {corridor:?} has {turns} turns");
+            assert!(corridor.path.windows(2).all(|step| step[0].0.abs_diff(step[1].0) + step[0].1.abs_diff(step[1].1) == 1));
+        }
+    }
+
+    #[test]
+    fn test_room_placement_count_and_spacing() {
+        for seed in 0..10 {
+            let dungeon = DungeonConfigBuilder::new()
+                .dungeon_size(DungeonSize { width: 60, height: 60 })
+                .room_size(RoomSize { min_room_size: 4, max_room_size: 8 })
+                .build_algorithm(RoomPlacementBuilder::new().max_attempts(500).target_room_count(6).min_spacing(3))
+                .seed(seed)
+                .build()
+                .expect("Failed to build dungeon");
+
+            assert_eq!(dungeon.rooms.len(), 6);
+            for (i, a) in dungeon.rooms.iter().enumerate() {
+                for b in &dungeon.rooms[(i + 1)..] {
+                    let gap_x = b.x.saturating_sub(a.x + a.width).max(a.x.saturating_sub(b.x + b.width));
+                    let gap_y = b.y.saturating_sub(a.y + a.height).max(a.y.saturating_sub(b.y + b.height));
+                    assert!(gap_x >= 3 || gap_y >= 3, "rooms {a:?} and {b:?} are too close");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_room_placement_extra_corridor_ratio() {
+        let build = |ratio, seed| DungeonConfigBuilder::new()
+            .dungeon_size(DungeonSize { width: 60, height: 60 })
+            .room_size(RoomSize { min_room_size: 4, max_room_size: 8 })
+            .build_algorithm(RoomPlacementBuilder::new().target_room_count(8).extra_corridor_ratio(ratio))
+            .seed(seed)
+            .build()
+            .expect("Failed to build dungeon");
+
+        for seed in 0..10 {
+            let tree = build(0.0, seed);
+            assert_eq!(tree.corridors.len(), tree.rooms.len() - 1);
+
+            let looped = build(1.0, seed);
+            let tree_edges = looped.rooms.len() - 1;
+            let all_edges = looped.rooms.len() * tree_edges / 2;
+            assert_eq!(looped.corridors.len(), (2 * tree_edges).min(all_edges));
+
+            let mut pairs: Vec<(usize, usize)> = looped.corridors
+                .iter()
+                .map(|c| (c.from_room.min(c.to_room), c.from_room.max(c.to_room)))
+                .collect();
+            pairs.sort();
+            pairs.dedup();
+            assert_eq!(pairs.len(), looped.corridors.len(), "rooms should be connected only once");
+        }
+    }
+
+    #[test]
+    fn test_room_placement_narrow_map() {
+        for (width, height) in [(1, 5), (5, 1), (2, 2), (3, 3)] {
+            let result = DungeonConfigBuilder::new()
+                .dungeon_size(DungeonSize { width, height })
+                .room_size(RoomSize { min_room_size: 1, max_room_size: 1 })
+                .build_algorithm(RoomPlacementBuilder::new())
+                .seed(1)
+                .build();
+            assert!(matches!(result, Err(DungeonBuildError::NoRoomsCreated)), "{width}x{height}: {result:?}");
+        }
+    }
+
+    #[test]
+    fn test_bsp_square_split_orientation_is_random() {
+        let mut vertical = 0;
+        let mut horizontal = 0;
+
+        for seed in 0..40 {
+            let dungeon = DungeonConfigBuilder::new()
+                .dungeon_size(DungeonSize { width: 40, height: 40 })
+                .room_size(RoomSize { min_room_size: 4, max_room_size: 10 })
+                .build_algorithm(BinaryPartitionBuilder::new().max_depth(1))
+                .seed(seed)
+                .build()
+                .expect("Failed to build dungeon");
+
+            let (first, second) = (&dungeon.rooms[0], &dungeon.rooms[1]);
+            if first.x + first.width <= second.x {
+                vertical += 1;
+            } else if first.y + first.height <= second.y {
+                horizontal += 1;
+            }
+        }
+
+        assert!(vertical > 0 && horizontal > 0, "vertical: {vertical}, horizontal: {horizontal}");
+    }
+
+    #[test]
+    fn test_bsp_every_leaf_has_room() {
+        for seed in 0..20 {
+            let dungeon = DungeonConfigBuilder::new()
+                .dungeon_size(DungeonSize { width: 64, height: 64 })
+                .room_size(RoomSize { min_room_size: 4, max_room_size: 6 })
+                .build_algorithm(BinaryPartitionBuilder::new().max_depth(3).stop_probability(0.0).split_ratio(0.5, 0.5))
+                .seed(seed)
+                .build()
+                .expect("Failed to build dungeon");
+
+            assert_eq!(dungeon.rooms.len(), 8);
+            assert_eq!(dungeon.corridors.len(), 7);
+        }
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn prop_bsp_rooms_are_connected_without_repair(
+            width in 8usize..80,
+            height in 8usize..80,
+            min_room_size in 2usize..7,
+            extra_room_size in 0usize..8,
+            room_padding in 0usize..3,
+            min_split_ratio in 0.0f64..0.5,
+            corridor_width in 1usize..3,
+            seed: u64,
+        ) {
+            let builder = BinaryPartitionBuilder::new()
+                .room_padding(room_padding)
+                .split_ratio(min_split_ratio, 1.0 - min_split_ratio)
+                .corridor_width(corridor_width);
+            let result = DungeonConfigBuilder::new()
+                .dungeon_size(DungeonSize { width, height })
+                .room_size(RoomSize { min_room_size, max_room_size: min_room_size + extra_room_size })
+                .build_algorithm(builder)
+                .connectivity(ConnectivityPolicy::Fail)
+                .seed(seed)
+                .build();
+
+            // root partition is too small for a room with padding
+            let min_part = min_room_size + 2 * room_padding + 1;
+            if width < min_part || height < min_part {
+                proptest::prop_assert!(matches!(result, Err(DungeonBuildError::NoRoomsCreated)));
+                return Ok(());
+            }
+
+            let dungeon = result.expect("Every leaf room should be reachable");
+            proptest::prop_assert_eq!(dungeon.corridors.len(), dungeon.rooms.len() - 1);
+
+            // corridors make a spanning tree over all rooms
+            let mut group: Vec<usize> = (0..dungeon.rooms.len()).collect();
+            for corridor in &dungeon.corridors {
+                let (from, to) = (group[corridor.from_room], group[corridor.to_room]);
+                proptest::prop_assert_ne!(from, to);
+                group.iter_mut().filter(|id| **id == to).for_each(|id| *id = from);
+            }
+            proptest::prop_assert!(group.iter().all(|&id| id == group[0]));
+
+            for room in &dungeon.rooms {
+                proptest::prop_assert!(room.width >= min_room_size && room.height >= min_room_size);
+                proptest::prop_assert!(room.x + room.width <= width && room.y + room.height <= height);
+            }
+        }
+    }
+
+    fn assert_valid_path(map: &grid::Grid<TileType>, path: &pathfinding::Path, adjacency: pathfinding::Adjacency) {
+        for step in path.tiles.windows(2) {
+            let ((x1, y1), (x2, y2)) = (step[0], step[1]);
+            let (dx, dy) = (x1.abs_diff(x2), y1.abs_diff(y2));
+            assert!(map[(x2, y2)].is_walkable(), "path enters wall at {:?}", step[1]);
+
+            match adjacency {
+                pathfinding::Adjacency::Four => assert_eq!(dx + dy, 1),
+                pathfinding::Adjacency::Eight => {
+                    assert!(dx <= 1 && dy <= 1 && dx + dy > 0);
+                    if dx + dy == 2 {
+                        assert!(map[(x2, y1)].is_walkable() && map[(x1, y2)].is_walkable(), "corner cut at {step:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_paths_between_room_centers() {
+        use pathfinding::{Adjacency, Pathfinder};
+
+        let dungeon = DungeonConfigBuilder::new()
+            .dungeon_size(DungeonSize { width: 60, height: 40 })
+            .build_algorithm(RoomPlacementBuilder::new())
+            .should_place_doors(true)
+            .seed(19)
+            .build()
+            .expect("Failed to build dungeon");
+        let four = Pathfinder::new();
+        let eight = Pathfinder::new().adjacency(Adjacency::Eight);
+
+        for from in &dungeon.rooms {
+            for to in &dungeon.rooms {
+                let path = four.astar(&dungeon.map, from.center, to.center).expect("Rooms should be connected");
+                assert_eq!(path.tiles.first(), Some(&from.center));
+                assert_eq!(path.tiles.last(), Some(&to.center));
+                assert_eq!(path.cost, (path.tiles.len() - 1) as f64);
+                assert_valid_path(&dungeon.map, &path, Adjacency::Four);
+
+                let dijkstra = four.dijkstra_path(&dungeon.map, from.center, to.center).expect("Rooms should be connected");
+                assert_eq!(dijkstra.cost, path.cost);
+
+                let diagonal = eight.astar(&dungeon.map, from.center, to.center).expect("Rooms should be connected");
+                assert_valid_path(&dungeon.map, &diagonal, Adjacency::Eight);
+                assert!(diagonal.cost <= path.cost);
+                let costs = eight.dijkstra(&dungeon.map, from.center);
+                assert!((costs[to.center].expect("Room should be reachable") - diagonal.cost).abs() < 1e-9);
+            }
+        }
+
+        assert_eq!(dungeon.find_path(dungeon.rooms[0].center, (0, 0)), None);
+    }
+
+    #[test]
+    fn test_path_costs() {
+        use pathfinding::{Adjacency, PathCosts, Pathfinder};
+
+        // door is a shortcut, but going around is cheaper when doors are expensive
+        let map = Dungeon::from_ascii("#######\n#.....#\n###+#.#\n#.....#\n#######\n").expect("Failed to parse map").map;
+        let (start, goal) = ((1, 3), (1, 1));
+
+        let through = Pathfinder::new().astar(&map, start, goal).expect("Goal should be reachable");
+        assert_eq!(through.cost, 6.0);
+        assert!(through.tiles.contains(&(3, 2)));
+
+        let costs = PathCosts { door: Some(10.0), ..Default::default() };
+        let around = Pathfinder::new().costs(costs).astar(&map, start, goal).expect("Goal should be reachable");
+        assert_eq!(around.cost, 10.0);
+        assert!(!around.tiles.contains(&(3, 2)));
+
+        let locked = PathCosts { door: None, ..Default::default() };
+        assert_eq!(Pathfinder::new().costs(locked).dijkstra(&map, start)[(3, 2)], None);
+
+        // diagonal steps around a single pillar can't cut its corners
+        let pillar = Dungeon::from_ascii("#####\n#...#\n#.#.#\n#...#\n#####\n").expect("Failed to parse map").map;
+        let path = Pathfinder::new().adjacency(Adjacency::Eight).astar(&pillar, (1, 1), (3, 3)).expect("Goal should be reachable");
+        assert_eq!(path.tiles.len(), 5);
+        assert_valid_path(&pillar, &path, Adjacency::Eight);
+        assert_eq!(path.cost, 4.0);
+    }
+
+    #[test]
+    fn test_distance_map_queries() {
+        use pathfinding::DistanceMap;
+
+        let dungeon = DungeonConfigBuilder::new()
+            .dungeon_size(DungeonSize { width: 50, height: 40 })
+            .build_algorithm(CellularAutomataBuilder::new())
+            .should_place_stairs(true)
+            .seed(20)
+            .build()
+            .expect("Failed to build dungeon");
+        let (entrance, exit) = (dungeon.entrance.expect("Entrance"), dungeon.exit.expect("Exit"));
+
+        let from_entrance = dungeon.distance_map(&[entrance]);
+        let from_exit = dungeon.distance_map(&[exit]);
+        let from_both = DistanceMap::new(&dungeon.map, &[entrance, exit]);
+
+        let (farthest, max_distance) = from_entrance.farthest().expect("Map should have reachable tiles");
+        assert_eq!(from_entrance.distance(exit.0, exit.1), Some(max_distance));
+        assert_eq!(from_entrance.distance(farthest.0, farthest.1), Some(max_distance));
+        assert_eq!(from_entrance.reachable().count(), dungeon.map.iter().filter(|tile| tile.is_walkable()).count());
+
+        for ((x, y), tile) in dungeon.map.cells() {
+            let expected = match (from_entrance.distance(x, y), from_exit.distance(x, y)) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                _ => None,
+            };
+            assert_eq!(from_both.distance(x, y), expected);
+            assert_eq!(expected.is_some(), tile.is_walkable());
+        }
+
+        // downhill from the exit leads back to the entrance by the shortest path
+        let path = from_entrance.downhill_path(&dungeon.map, exit);
+        assert_eq!(path.first(), Some(&exit));
+        assert_eq!(path.last(), Some(&entrance));
+        assert_eq!((path.len() - 1) as f64, max_distance);
+        assert!(path.windows(2).all(|step| step[0].0.abs_diff(step[1].0) + step[0].1.abs_diff(step[1].1) == 1));
+
+        let next = from_entrance.uphill_step(&dungeon.map, entrance).expect("Entrance should have neighbours");
+        assert_eq!(from_entrance.distance(next.0, next.1), Some(1.0));
+        assert_eq!(from_entrance.downhill_step(&dungeon.map, entrance), None);
+        assert!(from_entrance.downhill_path(&dungeon.map, (0, 0)).is_empty());
+    }
+
+    #[test]
+    fn test_distance_map_ranks_rooms_by_depth() {
+        let dungeon = DungeonConfigBuilder::new()
+            .dungeon_size(DungeonSize { width: 60, height: 40 })
+            .build_algorithm(RoomPlacementBuilder::new())
+            .should_place_stairs(true)
+            .seed(2)
+            .build()
+            .expect("Failed to build dungeon");
+        let distances = dungeon.distance_map(&[dungeon.entrance.expect("Entrance")]);
+
+        let mut rooms: Vec<&RoomInfo> = dungeon.rooms.iter().collect();
+        rooms.sort_by(|a, b| {
+            let depth = |room: &RoomInfo| distances.distance(room.center.0, room.center.1).unwrap_or(f64::INFINITY);
+            depth(a).total_cmp(&depth(b))
+        });
+
+        assert_eq!(Some(rooms[0].center), dungeon.entrance);
+        assert_eq!(rooms.last().map(|room| room.center), dungeon.exit);
+    }
+
+    #[test]
+    fn test_field_of_view_is_symmetric() {
+        let dungeon = DungeonConfigBuilder::new()
+            .dungeon_size(DungeonSize { width: 48, height: 36 })
+            .build_algorithm(CellularAutomataBuilder::new())
+            .seed(21)
+            .build()
+            .expect("Failed to build dungeon");
+        let floor: Vec<(usize, usize)> = dungeon.map
+            .cells()
+            .filter(|&(_, &tile)| tile == TileType::Floor)
+            .map(|(position, _)| position)
+            .step_by(11)
+            .collect();
+        let views: Vec<grid::Grid<bool>> = floor.iter().map(|&origin| dungeon.field_of_view(origin, 100)).collect();
+
+        for (i, &a) in floor.iter().enumerate() {
+            assert!(views[i][a]);
+            for (j, &b) in floor.iter().enumerate() {
+                assert_eq!(views[i][b], views[j][a], "visibility between {a:?} and {b:?} is not symmetric");
+            }
+
+            // sight never crosses walls, so every visible transparent tile is in the same open area
+            let mut open_area = grid::Grid::new(dungeon.map.width(), dungeon.map.height(), false);
+            let mut stack = vec![a];
+            open_area[a] = true;
+            while let Some((x, y)) = stack.pop() {
+                for neighbor in dungeon.map.neighbors8(x, y) {
+                    if !open_area[neighbor] && !fov::is_opaque(dungeon.map[neighbor]) {
+                        open_area[neighbor] = true;
+                        stack.push(neighbor);
+                    }
+                }
+            }
+            for (position, &is_visible) in views[i].cells() {
+                if is_visible && !fov::is_opaque(dungeon.map[position]) {
+                    assert!(open_area[position], "{position:?} is visible from {a:?} through walls");
+                }
+            }
+        }
+
+        for &b in floor.iter().take(20) {
+            assert_eq!(dungeon.line_of_sight(floor[0], b), dungeon.line_of_sight(b, floor[0]));
+        }
+    }
+
+    #[test]
+    fn test_field_of_view_rooms_and_doors() {
+        let dungeon = Dungeon::from_ascii(
+            "###########\n\
+             #.....#...#\n\
+             #.....+...#\n\
+             #.....#...#\n\
+             ###########\n"
+        ).expect("Failed to parse map");
+        let visible = dungeon.field_of_view((3, 2), 20);
+
+        for ((x, y), &is_visible) in visible.cells() {
+            let in_room = x <= 6;
+            assert_eq!(is_visible, in_room, "tile ({x}, {y}) visibility");
+        }
+        assert!(visible[(6, 2)], "door is visible, but hides the room behind it");
+        assert!(!dungeon.line_of_sight((3, 2), (8, 2)));
+        assert!(dungeon.line_of_sight((1, 1), (5, 3)));
+
+        let short = dungeon.field_of_view((1, 2), 2);
+        assert!(short[(3, 2)] && short[(2, 3)]);
+        assert!(!short[(4, 2)] && !short[(3, 3)]);
+
+        for radius in [usize::MAX, 1 << 40] {
+            assert_eq!(dungeon.field_of_view((3, 2), radius), visible, "radius {radius}");
+        }
+    }
+
+    #[test]
+    fn test_populator_room_rules() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let dungeon = DungeonConfigBuilder::new()
+            .dungeon_size(DungeonSize { width: 60, height: 40 })
+            .build_algorithm(RoomPlacementBuilder::new())
+            .should_place_stairs(true)
+            .seed(5)
+            .build()
+            .expect("Failed to build dungeon");
+        let entrance = dungeon.entrance.expect("Entrance");
+        let table = SpawnTable::new().entry("goblin", 3).entry("orc", 1).entry("dragon", 0);
+        let populator = Populator::new(table)
+            .per_room(1, 3)
+            .density(0.1)
+            .min_distance_from_start(6.0);
+        let spawns = populator.populate(&dungeon, &mut StdRng::seed_from_u64(1));
+        assert!(!spawns.is_empty());
+
+        let distances = dungeon.distance_map(&[entrance]);
+        for spawn in &spawns {
+            let room = &dungeon.rooms[spawn.room.expect("Spawn should be in a room")];
+            assert!(room.contains(spawn.position.0, spawn.position.1));
+            assert!(!room.contains(entrance.0, entrance.1), "entrance room should stay empty");
+            assert_eq!(dungeon.map[spawn.position], TileType::Floor);
+            assert!(distances.distance(spawn.position.0, spawn.position.1).is_some_and(|distance| distance >= 6.0));
+            assert_ne!(spawn.payload, "dragon");
+        }
+
+        for room in &dungeon.rooms {
+            let count = spawns.iter().filter(|spawn| spawn.room == Some(room.id)).count();
+            assert!(count <= 3, "room {} has {count} spawns", room.id);
+        }
+
+        let mut positions: Vec<(usize, usize)> = spawns.iter().map(|spawn| spawn.position).collect();
+        positions.sort();
+        positions.dedup();
+        assert_eq!(positions.len(), spawns.len(), "tile should get at most one spawn");
+
+        assert_eq!(spawns, populator.populate(&dungeon, &mut StdRng::seed_from_u64(1)));
+    }
+
+    #[test]
+    fn test_populator_counts_and_weights() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let dungeon = Dungeon::from_ascii(
+            "############\n\
+             #....#.....#\n\
+             #.<..+.....#\n\
+             #....#.....#\n\
+             ############\n"
+        ).expect("Failed to parse map");
+        let mut rng = StdRng::seed_from_u64(3);
+
+        // map without rooms is populated as one area
+        let spawns = Populator::new(SpawnTable::new().entry('g', 1)).density(0.5).populate(&dungeon, &mut rng);
+        assert_eq!(spawns.len(), 13);
+        assert!(spawns.iter().all(|spawn| spawn.room.is_none() && dungeon.map[spawn.position] == TileType::Floor));
+
+        let far = Populator::new(SpawnTable::new().entry('g', 1))
+            .per_room(100, 100)
+            .min_distance_from_start(5.0)
+            .populate(&dungeon, &mut rng);
+        assert_eq!(far.len(), 14, "only the far side of the door is far enough from start");
+        assert!(far.iter().all(|spawn| spawn.position.0 >= 6));
+
+        assert!(Populator::new(SpawnTable::<char>::new()).per_room(5, 5).populate(&dungeon, &mut rng).is_empty());
+
+        let table = SpawnTable::new().entry('a', 1).entry('b', 3);
+        let picks: Vec<char> = (0..4000).filter_map(|_| table.pick(&mut rng)).collect();
+        let b_count = picks.iter().filter(|&&payload| payload == 'b').count();
+        assert_eq!(picks.len(), 4000);
+        assert!((2800..3200).contains(&b_count), "'b' was picked {b_count} times");
+    }
+
+    #[test]
+    fn test_room_tagger_rules() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        // 0 - 1 - 2 - 3
+        //     |
+        //     4
+        let rooms = vec![
+            RoomInfo::new(0, 1, 1, 4, 4),
+            RoomInfo::new(1, 7, 1, 4, 4),
+            RoomInfo::new(2, 13, 1, 6, 6),
+            RoomInfo::new(3, 21, 1, 4, 4),
+            RoomInfo::new(4, 7, 9, 3, 3),
+        ];
+        let corridors = [(0, 1), (1, 2), (2, 3), (1, 4)]
+            .into_iter()
+            .map(|(from, to)| CorridorInfo::new(from, to, vec![rooms[from].center, rooms[to].center]))
+            .collect();
+        let mut dungeon = Dungeon {
+            map: grid::Grid::new(26, 14, TileType::Floor),
+            rooms,
+            corridors,
+            entrance: Some((2, 2)),
+            exit: None,
+        };
+        let tagger = RoomTagger::new()
+            .rule(TagRule::new(RoomTag::Boss, 1).dead_end(true).depth(2, usize::MAX).max_count(1))
+            .rule(TagRule::new(RoomTag::Treasure, 1).dead_end(true).depth(1, 2))
+            .rule(TagRule::new(RoomTag::Shop, 1).area(30, usize::MAX));
+
+        for seed in 0..10 {
+            dungeon.tag_rooms(&tagger, &mut StdRng::seed_from_u64(seed));
+            let tags: Vec<Option<RoomTag>> = dungeon.rooms.iter().map(|room| room.tag).collect();
+            assert_eq!(tags, vec![
+                Some(RoomTag::Start),
+                Some(RoomTag::Empty),
+                Some(RoomTag::Shop),
+                Some(RoomTag::Boss),
+                Some(RoomTag::Treasure),
+            ]);
+        }
+        assert_eq!(dungeon.rooms_with_tag(RoomTag::Boss).map(|room| room.id).collect::<Vec<_>>(), vec![3]);
+
+        // without entrance the first room is the start
+        dungeon.entrance = None;
+        dungeon.tag_rooms(&RoomTagger::new(), &mut StdRng::seed_from_u64(0));
+        assert_eq!(dungeon.rooms[0].tag, Some(RoomTag::Start));
+        assert_eq!(dungeon.rooms_with_tag(RoomTag::Empty).count(), 4);
+    }
+
+    #[test]
+    fn test_room_tagger_corridor_through_room() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        // corridor from 0 to 2 passes through room 1, so 1 is joined with 0, 2 and 3
+        // 0 - 1 - 2
+        //     |
+        //     3
+        let rooms = vec![
+            RoomInfo::new(0, 1, 1, 3, 3),
+            RoomInfo::new(1, 6, 1, 3, 3),
+            RoomInfo::new(2, 11, 1, 3, 3),
+            RoomInfo::new(3, 6, 6, 3, 3),
+        ];
+        let corridors = vec![
+            CorridorInfo::new(0, 2, (2..=12).map(|x| (x, 2)).collect()),
+            CorridorInfo::new(1, 3, (2..=7).map(|y| (7, y)).collect()),
+        ];
+        let mut dungeon = Dungeon {
+            map: grid::Grid::new(15, 10, TileType::Floor),
+            rooms,
+            corridors,
+            entrance: Some((2, 2)),
+            exit: None,
+        };
+        let tagger = RoomTagger::new().rule(TagRule::new(RoomTag::Treasure, 1).dead_end(true).depth(2, 2));
+
+        dungeon.tag_rooms(&tagger, &mut StdRng::seed_from_u64(0));
+        let tags: Vec<Option<RoomTag>> = dungeon.rooms.iter().map(|room| room.tag).collect();
+        assert_eq!(tags, vec![
+            Some(RoomTag::Start),
+            Some(RoomTag::Empty),
+            Some(RoomTag::Treasure),
+            Some(RoomTag::Treasure),
+        ]);
+    }
+
+    #[test]
+    fn test_room_tagger_weights_and_limits() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let mut dungeon = DungeonConfigBuilder::new()
+            .dungeon_size(DungeonSize { width: 80, height: 50 })
+            .build_algorithm(RoomPlacementBuilder::new())
+            .should_place_stairs(true)
+            .seed(9)
+            .build()
+            .expect("Failed to build dungeon");
+        let tagger = RoomTagger::new()
+            .rule(TagRule::new(RoomTag::Boss, 1).max_count(1))
+            .rule(TagRule::new(RoomTag::Treasure, 3).max_count(2))
+            .rule(TagRule::new(RoomTag::Shop, 0))
+            .rule(TagRule::new(RoomTag::Empty, 1));
+        dungeon.tag_rooms(&tagger, &mut StdRng::seed_from_u64(4));
+
+        let entrance = dungeon.entrance.expect("Entrance");
+        let start: Vec<&RoomInfo> = dungeon.rooms_with_tag(RoomTag::Start).collect();
+        assert_eq!(start.len(), 1);
+        assert!(start[0].contains(entrance.0, entrance.1));
+        assert!(dungeon.rooms.iter().all(|room| room.tag.is_some()));
+        assert!(dungeon.rooms_with_tag(RoomTag::Boss).count() <= 1);
+        assert!(dungeon.rooms_with_tag(RoomTag::Treasure).count() <= 2);
+        assert_eq!(dungeon.rooms_with_tag(RoomTag::Shop).count(), 0, "zero weight rule is never picked");
+
+        let untagged = DungeonConfigBuilder::new()
+            .dungeon_size(DungeonSize { width: 80, height: 50 })
+            .build_algorithm(RoomPlacementBuilder::new())
+            .should_place_stairs(true)
+            .seed(9)
+            .build()
+            .expect("Failed to build dungeon");
+        assert!(untagged.rooms.iter().all(|room| room.tag.is_none()));
+        assert_eq!(untagged.map, dungeon.map, "tagging doesn't change the map");
+    }
+
+    fn room_graph_test_dungeon(links: &[(usize, usize, usize)], room_count: usize) -> Dungeon {
+        let rooms = (0..room_count).map(|i| RoomInfo::new(i, i * 4, 0, 3, 3)).collect();
+        let corridors = links
+            .iter()
+            .map(|&(from, to, length)| CorridorInfo::new(from, to, vec![(0, 3); length]))
+            .collect();
+        Dungeon { map: grid::Grid::new(room_count * 4, 4, TileType::Wall), rooms, corridors, entrance: None, exit: None }
+    }
+
+    #[test]
+    fn test_room_graph_queries() {
+        // triangle 0 1 2 with a tail 2 - 3 - 4 and a separate pair 5 = 6 with two corridors
+        let dungeon = room_graph_test_dungeon(
+            &[(0, 1, 10), (1, 2, 3), (2, 0, 4), (2, 3, 5), (3, 4, 1), (5, 6, 2), (6, 5, 2), (4, 4, 1)],
+            7,
+        );
+        let graph = dungeon.room_graph();
+
+        assert_eq!(graph.room_count(), 7);
+        assert_eq!(graph.edges().len(), 7, "corridor from a room to itself is skipped");
+        assert_eq!((0..7).map(|room| graph.degree(room)).collect::<Vec<_>>(), vec![2, 2, 3, 2, 1, 2, 2]);
+        assert!(graph.is_dead_end(4));
+        assert_eq!(graph.neighbours(2).collect::<Vec<_>>(), vec![1, 0, 3]);
+
+        let path = graph.shortest_path(0, 4).expect("Rooms should be connected");
+        assert_eq!(path, RoomPath { rooms: vec![0, 2, 3, 4], length: 10 });
+        assert_eq!(graph.shortest_path(1, 0).map(|path| path.rooms), Some(vec![1, 2, 0]));
+        assert_eq!(graph.shortest_path(3, 3).map(|path| path.length), Some(0));
+        assert_eq!(graph.shortest_path(0, 5), None);
+        assert_eq!(graph.depths(0), vec![Some(0), Some(1), Some(1), Some(2), Some(3), None, None]);
+
+        assert_eq!(graph.articulation_points(), vec![2, 3]);
+        let bridges: Vec<(usize, usize)> = graph.bridges()
+            .iter()
+            .map(|&edge| (graph.edges()[edge].from, graph.edges()[edge].to))
+            .collect();
+        assert_eq!(bridges, vec![(2, 3), (3, 4)]);
+        assert!(graph.has_cycle());
+        assert_eq!(graph.cycle_edges(), vec![0, 1, 2, 5, 6], "parallel corridors form a cycle");
+
+        let tree = room_graph_test_dungeon(&[(0, 1, 1), (1, 2, 1), (1, 3, 1)], 4).room_graph();
+        assert!(!tree.has_cycle());
+        assert_eq!(tree.articulation_points(), vec![1]);
+        assert_eq!(tree.bridges().len(), 3);
+
+        // corridor from 0 to 2 passes through room 1, one corridor tile on each side of it
+        let mut crossing = room_graph_test_dungeon(&[], 3);
+        crossing.corridors.push(CorridorInfo::new(0, 2, (1..=9).map(|x| (x, 1)).collect()));
+        let graph = crossing.room_graph();
+        assert_eq!(graph.edges(), &[
+            RoomEdge { from: 0, to: 1, length: 1, corridor: 0 },
+            RoomEdge { from: 1, to: 2, length: 1, corridor: 0 },
+        ]);
+        assert_eq!(graph.degree(1), 2);
+    }
+
+    #[test]
+    fn test_room_graph_of_built_dungeons() {
+        let dungeon = DungeonConfigBuilder::new()
+            .dungeon_size(DungeonSize { width: 80, height: 50 })
+            .build_algorithm(RoomPlacementBuilder::new().extra_corridor_ratio(0.0))
+            .seed(6)
+            .build()
+            .expect("Failed to build dungeon");
+        let graph = dungeon.room_graph();
+        assert_eq!(graph.edges().len(), dungeon.rooms.len() - 1, "minimum spanning tree has no extra corridors");
+        assert!(!graph.has_cycle());
+        assert!(graph.depths(0).iter().all(Option::is_some));
+        for (i, edge) in graph.edges().iter().enumerate() {
+            let outside_rooms = dungeon.corridors[i].path
+                .iter()
+                .filter(|&&(x, y)| !dungeon.rooms.iter().any(|room| room.contains(x, y)))
+                .count();
+            assert_eq!(edge.corridor, i);
+            assert_eq!(edge.length, outside_rooms);
+        }
+
+        let looped = DungeonConfigBuilder::new()
+            .dungeon_size(DungeonSize { width: 80, height: 50 })
+            .build_algorithm(RoomPlacementBuilder::new().extra_corridor_ratio(0.5))
+            .seed(6)
+            .build()
+            .expect("Failed to build dungeon")
+            .room_graph();
+        assert!(looped.has_cycle());
+        assert!(looped.cycle_edges().len() >= 3);
+
+        let first = looped.shortest_path(0, looped.room_count() - 1).expect("Rooms should be connected");
+        assert_eq!(first.rooms.first(), Some(&0));
+        assert_eq!(first.rooms.last(), Some(&(looped.room_count() - 1)));
+        assert!(first.rooms.windows(2).all(|pair| looped.neighbours(pair[0]).any(|room| room == pair[1])));
+    }
+
+    /// walks from `start` collecting keys and opening their doors, returns walkable tiles left unreached
+    fn unreachable_after_collecting_keys(dungeon: &Dungeon, start: (usize, usize), locks: &[Lock]) -> usize {
+        let mut map = dungeon.map.clone();
+        loop {
+            let distances = pathfinding::DistanceMap::new(&map, &[start]);
+            let opened: Vec<&Lock> = locks
+                .iter()
+                .filter(|lock| map[lock.door] == TileType::LockedDoor)
+                .filter(|lock| distances.distance(lock.key.0, lock.key.1).is_some())
+                .collect();
+
+            if opened.is_empty() {
+                return map
+                    .cells()
+                    .filter(|&(_, &tile)| tile.is_walkable() || tile == TileType::LockedDoor)
+                    .filter(|&(position, _)| distances.distance(position.0, position.1).is_none())
+                    .count();
+            }
+
+            for lock in opened {
+                map[lock.door] = TileType::Door;
+            }
+        }
+    }
+
+    #[test]
+    fn test_locks_on_room_chain() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let mut dungeon = Dungeon::from_ascii(
+            "#################\n\
+             #...#.....#.....#\n\
+             #.<...........>.#\n\
+             #...#.....#.....#\n\
+             #################\n"
+        ).expect("Failed to parse map");
+        dungeon.rooms = vec![RoomInfo::new(0, 1, 1, 3, 3), RoomInfo::new(1, 5, 1, 5, 3), RoomInfo::new(2, 11, 1, 5, 3)];
+        dungeon.corridors = vec![
+            CorridorInfo::new(0, 1, vec![(3, 2), (4, 2), (5, 2)]),
+            CorridorInfo::new(1, 2, vec![(9, 2), (10, 2), (11, 2)]),
+        ];
+        let original = dungeon.clone();
+
+        let locks = dungeon.place_locks(&LockPlacer::new().lock_count(5), &mut StdRng::seed_from_u64(1));
+        assert_eq!(locks.len(), 2, "only two corridors can't be bypassed");
+        let mut doors: Vec<(usize, usize)> = locks.iter().map(|lock| lock.door).collect();
+        doors.sort();
+        assert_eq!(doors, vec![(4, 2), (10, 2)]);
+
+        for lock in &locks {
+            assert_eq!(dungeon.map[lock.door], TileType::LockedDoor);
+            assert_eq!(dungeon.map[lock.key], TileType::Floor);
+            assert!(dungeon.rooms[lock.key_room].contains(lock.key.0, lock.key.1));
+            assert!(lock.key.0 < lock.door.0, "key should be on the entrance side of its door");
+        }
+        assert!(dungeon.is_solvable(&locks));
+        assert_eq!(unreachable_after_collecting_keys(&dungeon, (2, 2), &locks), 0);
+
+        // key behind its own door can't be picked up
+        let mut swapped = locks.clone();
+        let first_door = swapped.iter().position(|lock| lock.door == (4, 2)).expect("Lock on the first corridor");
+        swapped[first_door].key = (7, 2);
+        assert!(!dungeon.is_solvable(&swapped));
+        assert!(unreachable_after_collecting_keys(&dungeon, (2, 2), &swapped) > 0);
+
+        let mut looped = original;
+        looped.corridors.push(CorridorInfo::new(0, 1, vec![(3, 2), (4, 2), (5, 2)]));
+        let locks = looped.place_locks(&LockPlacer::new().lock_count(5), &mut StdRng::seed_from_u64(1));
+        let doors: Vec<(usize, usize)> = locks.iter().map(|lock| lock.door).collect();
+        assert_eq!(doors, vec![(10, 2)], "parallel corridors are not locked");
+    }
+
+    #[test]
+    fn test_locks_keep_built_dungeons_solvable() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        for seed in 0..20 {
+            let mut dungeon = DungeonConfigBuilder::new()
+                .dungeon_size(DungeonSize { width: 64, height: 48 })
+                .build_algorithm(BinaryPartitionBuilder::new())
+                .should_place_doors(seed % 2 == 0)
+                .should_place_stairs(true)
+                .seed(seed)
+                .build()
+                .expect("Failed to build dungeon");
+            let locks = dungeon.place_locks(&LockPlacer::new().lock_count(3), &mut StdRng::seed_from_u64(seed));
+            assert!(!locks.is_empty(), "seed {seed} has no locks");
+
+            let locked_doors = dungeon.map.iter().filter(|&&tile| tile == TileType::LockedDoor).count();
+            assert_eq!(locked_doors, locks.len());
+            assert!(dungeon.is_solvable(&locks), "seed {seed} is not solvable");
+            let entrance = dungeon.entrance.expect("Entrance");
+            assert_eq!(unreachable_after_collecting_keys(&dungeon, entrance, &locks), 0, "seed {seed}");
+
+            // with all doors locked some rooms can't be reached
+            let distances = dungeon.distance_map(&[entrance]);
+            assert!(dungeon.rooms.iter().any(|room| distances.distance(room.center.0, room.center.1).is_none()));
+            for lock in &locks {
+                assert!(fov::is_opaque(dungeon.map[lock.door]));
+                assert_eq!(dungeon.map[lock.key], TileType::Floor);
+            }
+        }
+    }
+
+    #[test]
+    fn test_build_with_rng() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let seeded = DungeonConfigBuilder::new()
+            .build_algorithm(BinaryPartitionBuilder::new())
+            .seed(7)
+            .build()
+            .expect("Failed to build dungeon");
+        let with_rng = DungeonConfigBuilder::new()
+            .build_algorithm(BinaryPartitionBuilder::new())
+            .build_with_rng(&mut StdRng::seed_from_u64(7))
+            .expect("Failed to build dungeon");
+        assert_eq!(seeded, with_rng);
+    }
 }
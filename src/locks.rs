@@ -0,0 +1,196 @@
+use rand::seq::SliceRandom;
+use rand::RngCore;
+use crate::dungeon::{Dungeon, TileType};
+use crate::grid::Grid;
+use crate::room_graph::RoomGraph;
+
+/// Locked door and the key which opens it, `id` is an index in the list returned by `LockPlacer`
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Lock {
+    pub id: usize,
+    /// position of `TileType::LockedDoor`
+    pub door: (usize, usize),
+    /// index of the locked corridor in `Dungeon::corridors`
+    pub corridor: usize,
+    pub key: (usize, usize),
+    pub key_room: usize,
+}
+
+/// Locks corridors which are the only way into some rooms and places a key for every door
+/// in a room reachable without passing through that door. Players start at the entrance,
+/// or in the center of the first room when stairs are not placed
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LockPlacer {
+    lock_count: usize,
+}
+
+impl LockPlacer {
+    pub fn new() -> Self {
+        Self { lock_count: 1 }
+    }
+
+    /// fewer locks are placed when dungeon doesn't have enough corridors which can't be bypassed
+    pub fn lock_count(mut self, lock_count: usize) -> Self {
+        self.lock_count = lock_count;
+        self
+    }
+
+    /// Turns one tile of every locked corridor into `TileType::LockedDoor`,
+    /// every placed lock keeps the dungeon solvable
+    pub fn place(&self, dungeon: &mut Dungeon, rng: &mut dyn RngCore) -> Vec<Lock> {
+        let Some(start) = start_position(dungeon) else {
+            return Vec::new();
+        };
+
+        let graph = RoomGraph::new(dungeon);
+        let mut bridges = graph.bridges();
+        bridges.shuffle(rng);
+
+        let mut locks = Vec::new();
+        for edge in bridges {
+            if locks.len() >= self.lock_count {
+                break;
+            }
+
+            let corridor = graph.edges()[edge].corridor;
+            if let Some(lock) = lock_corridor(dungeon, corridor, start, &locks, rng) {
+                locks.push(lock);
+            }
+        }
+
+        locks
+    }
+}
+
+impl Default for LockPlacer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dungeon {
+    pub fn place_locks(&mut self, placer: &LockPlacer, rng: &mut dyn RngCore) -> Vec<Lock> {
+        placer.place(self, rng)
+    }
+
+    /// Simulates a player who walks from the start, picks up every reachable key and opens its door.
+    /// Dungeon is solvable when every walkable tile and every locked door is reached in the end
+    pub fn is_solvable(&self, locks: &[Lock]) -> bool {
+        let Some(start) = start_position(self) else {
+            return locks.is_empty();
+        };
+
+        let mut has_key = vec![false; locks.len()];
+        loop {
+            let is_open = |position| locks.iter().zip(&has_key).any(|(lock, &has_key)| has_key && lock.door == position);
+            let reachable = flood(&self.map, start, |position, tile| tile.is_walkable() || is_open(position));
+
+            let mut found = false;
+            for (i, lock) in locks.iter().enumerate() {
+                if !has_key[i] && reachable.get(lock.key.0, lock.key.1) == Some(&true) {
+                    has_key[i] = true;
+                    found = true;
+                }
+            }
+
+            if !found {
+                return self.map
+                    .cells()
+                    .filter(|&(_, &tile)| tile.is_walkable() || tile == TileType::LockedDoor)
+                    .all(|(position, _)| reachable[position]);
+            }
+        }
+    }
+}
+
+fn start_position(dungeon: &Dungeon) -> Option<(usize, usize)> {
+    dungeon.entrance.or(dungeon.rooms.first().map(|room| room.center))
+}
+
+/// Tries corridor tiles outside of rooms, existing doors first, until locking one of them
+/// cuts off some rooms, keeps keys of other locks reachable and the dungeon solvable
+fn lock_corridor(
+    dungeon: &mut Dungeon,
+    corridor: usize,
+    start: (usize, usize),
+    locks: &[Lock],
+    rng: &mut dyn RngCore,
+) -> Option<Lock> {
+    let mut doors: Vec<(usize, usize)> = dungeon.corridors[corridor].path
+        .iter()
+        .copied()
+        .filter(|&position| matches!(dungeon.map[position], TileType::Floor | TileType::Door))
+        .filter(|&(x, y)| !dungeon.rooms.iter().any(|room| room.contains(x, y)))
+        .collect();
+    doors.sort_by_key(|&position| dungeon.map[position] != TileType::Door);
+
+    for door in doors {
+        // other locks are open, so only rooms cut off by this door are unreachable
+        let reachable = flood(&dungeon.map, start, |position, tile| {
+            position != door && (tile.is_walkable() || tile == TileType::LockedDoor)
+        });
+
+        let cuts_off_rooms = dungeon.rooms.iter().any(|room| !reachable[room.center]);
+        if !cuts_off_rooms || locks.iter().any(|lock| !reachable[lock.key]) {
+            continue;
+        }
+
+        let mut key_rooms: Vec<usize> = (0..dungeon.rooms.len())
+            .filter(|&id| reachable[dungeon.rooms[id].center])
+            .collect();
+        key_rooms.shuffle(rng);
+
+        let key = key_rooms.into_iter().find_map(|id| {
+            let room = dungeon.rooms[id];
+            let tiles: Vec<(usize, usize)> = (room.y..(room.y + room.height))
+                .flat_map(|y| (room.x..(room.x + room.width)).map(move |x| (x, y)))
+                .filter(|&position| dungeon.map[position] == TileType::Floor && reachable[position])
+                .filter(|&position| locks.iter().all(|lock| lock.key != position))
+                .collect();
+            tiles.choose(rng).map(|&key| (id, key))
+        });
+        let Some((key_room, key)) = key else {
+            continue;
+        };
+
+        let tile = dungeon.map[door];
+        dungeon.map[door] = TileType::LockedDoor;
+        let lock = Lock { id: locks.len(), door, corridor, key, key_room };
+
+        let mut with_lock = locks.to_vec();
+        with_lock.push(lock);
+        if dungeon.is_solvable(&with_lock) {
+            return Some(lock);
+        }
+        dungeon.map[door] = tile;
+    }
+
+    None
+}
+
+/// 4-connected flood fill from `start`, start tile is always reached
+fn flood(
+    map: &Grid<TileType>,
+    start: (usize, usize),
+    passable: impl Fn((usize, usize), TileType) -> bool,
+) -> Grid<bool> {
+    let mut reached = Grid::new(map.width(), map.height(), false);
+    if !map.in_bounds(start.0, start.1) {
+        return reached;
+    }
+
+    let mut stack = vec![start];
+    reached[start] = true;
+    while let Some((x, y)) = stack.pop() {
+        for next in map.neighbors4(x, y) {
+            if !reached[next] && passable(next, map[next]) {
+                reached[next] = true;
+                stack.push(next);
+            }
+        }
+    }
+
+    reached
+}
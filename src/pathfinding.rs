@@ -0,0 +1,363 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::f64::consts::SQRT_2;
+use crate::dungeon::{Dungeon, TileType};
+use crate::grid::Grid;
+
+/// Which tiles are adjacent to each other
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Adjacency {
+    /// orthogonal steps only
+    Four,
+    /// orthogonal and diagonal steps, diagonal step costs `sqrt(2)` times more
+    /// and is not allowed when it cuts a corner of impassable tile
+    Eight,
+}
+
+/// Cost to enter every tile type, `None` means the tile can't be entered
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PathCosts {
+    pub wall: Option<f64>,
+    pub floor: Option<f64>,
+    pub door: Option<f64>,
+    pub stairs_up: Option<f64>,
+    pub stairs_down: Option<f64>,
+    pub locked_door: Option<f64>,
+}
+
+impl PathCosts {
+    pub fn cost(&self, tile: TileType) -> Option<f64> {
+        match tile {
+            TileType::Wall => self.wall,
+            TileType::Floor => self.floor,
+            TileType::Door => self.door,
+            TileType::StairsUp => self.stairs_up,
+            TileType::StairsDown => self.stairs_down,
+            TileType::LockedDoor => self.locked_door,
+        }
+    }
+
+    fn min_cost(&self) -> f64 {
+        [self.wall, self.floor, self.door, self.stairs_up, self.stairs_down, self.locked_door]
+            .into_iter()
+            .flatten()
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+impl Default for PathCosts {
+    /// every walkable tile costs 1, walls and locked doors are impassable
+    fn default() -> Self {
+        Self {
+            wall: None,
+            floor: Some(1.0),
+            door: Some(1.0),
+            stairs_up: Some(1.0),
+            stairs_down: Some(1.0),
+            locked_door: None,
+        }
+    }
+}
+
+/// Path from start to goal including both ends
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    pub tiles: Vec<(usize, usize)>,
+    /// sum of costs of all entered tiles, start tile is not counted
+    pub cost: f64,
+}
+
+/// A* and Dijkstra search over the dungeon map
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Pathfinder {
+    costs: PathCosts,
+    adjacency: Adjacency,
+}
+
+impl Pathfinder {
+    pub fn new() -> Self {
+        Self {
+            costs: PathCosts::default(),
+            adjacency: Adjacency::Four,
+        }
+    }
+
+    /// Negative costs are not supported
+    pub fn costs(mut self, costs: PathCosts) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    pub fn adjacency(mut self, adjacency: Adjacency) -> Self {
+        self.adjacency = adjacency;
+        self
+    }
+
+    /// Cheapest path with A*, `None` when goal is not reachable.
+    /// Start tile doesn't need to be passable
+    pub fn astar(&self, map: &Grid<TileType>, start: (usize, usize), goal: (usize, usize)) -> Option<Path> {
+        let min_cost = match self.costs.min_cost() {
+            cost if cost.is_finite() => cost,
+            _ => 0.0,
+        };
+        let adjacency = self.adjacency;
+
+        let heuristic = move |(x, y): (usize, usize)| {
+            let dx = x.abs_diff(goal.0) as f64;
+            let dy = y.abs_diff(goal.1) as f64;
+            let distance = match adjacency {
+                Adjacency::Four => dx + dy,
+                Adjacency::Eight => dx.max(dy) + (SQRT_2 - 1.0) * dx.min(dy),
+            };
+            distance * min_cost
+        };
+
+        let search = self.search(map, &[start], Some(goal), heuristic);
+        search.path(goal)
+    }
+
+    /// Cheapest path with Dijkstra, gives the same cost as `astar`
+    pub fn dijkstra_path(&self, map: &Grid<TileType>, start: (usize, usize), goal: (usize, usize)) -> Option<Path> {
+        let search = self.search(map, &[start], Some(goal), |_| 0.0);
+        search.path(goal)
+    }
+
+    /// Cost of the cheapest path from start to every tile, `None` for unreachable tiles
+    pub fn dijkstra(&self, map: &Grid<TileType>, start: (usize, usize)) -> Grid<Option<f64>> {
+        self.dijkstra_multi(map, &[start])
+    }
+
+    /// Cost of the cheapest path from the closest of the sources to every tile
+    pub fn dijkstra_multi(&self, map: &Grid<TileType>, sources: &[(usize, usize)]) -> Grid<Option<f64>> {
+        self.search(map, sources, None, |_| 0.0).costs
+    }
+
+    /// Positions which can be entered from `(x, y)` with the cost of the step
+    pub fn steps<'a>(
+        &'a self,
+        map: &'a Grid<TileType>,
+        (x, y): (usize, usize),
+    ) -> impl Iterator<Item = ((usize, usize), f64)> + 'a {
+        let offsets: &[(isize, isize)] = match self.adjacency {
+            Adjacency::Four => &[(0, -1), (-1, 0), (1, 0), (0, 1)],
+            Adjacency::Eight => &[(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)],
+        };
+
+        let passable = move |x: usize, y: usize| map.get(x, y).and_then(|&tile| self.costs.cost(tile));
+
+        offsets.iter().filter_map(move |&(dx, dy)| {
+            let nx = x.checked_add_signed(dx)?;
+            let ny = y.checked_add_signed(dy)?;
+            let cost = passable(nx, ny)?;
+
+            if dx != 0 && dy != 0 {
+                // diagonal step can't cut corners
+                passable(nx, y)?;
+                passable(x, ny)?;
+                return Some(((nx, ny), cost * SQRT_2));
+            }
+
+            Some(((nx, ny), cost))
+        })
+    }
+
+    fn search(
+        &self,
+        map: &Grid<TileType>,
+        sources: &[(usize, usize)],
+        goal: Option<(usize, usize)>,
+        heuristic: impl Fn((usize, usize)) -> f64,
+    ) -> Search {
+        let mut costs = Grid::new(map.width(), map.height(), None);
+        let mut previous = Grid::new(map.width(), map.height(), None);
+        let mut open = BinaryHeap::new();
+
+        for &source in sources {
+            if map.in_bounds(source.0, source.1) {
+                costs[source] = Some(0.0);
+                open.push(Node { priority: heuristic(source), cost: 0.0, position: source });
+            }
+        }
+
+        while let Some(Node { cost, position, .. }) = open.pop() {
+            if costs[position].is_some_and(|best| cost > best) {
+                continue;
+            }
+
+            if goal == Some(position) {
+                break;
+            }
+
+            for (next, step_cost) in self.steps(map, position) {
+                let next_cost = cost + step_cost;
+                if costs[next].is_none_or(|best| next_cost < best) {
+                    costs[next] = Some(next_cost);
+                    previous[next] = Some(position);
+                    open.push(Node { priority: next_cost + heuristic(next), cost: next_cost, position: next });
+                }
+            }
+        }
+
+        Search { costs, previous }
+    }
+}
+
+impl Default for Pathfinder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dungeon {
+    /// Shortest walkable path with 4-connectivity, see `Pathfinder` for other costs
+    pub fn find_path(&self, start: (usize, usize), goal: (usize, usize)) -> Option<Path> {
+        Pathfinder::new().astar(&self.map, start, goal)
+    }
+
+    /// Step distances over walkable tiles from the closest of the sources
+    pub fn distance_map(&self, sources: &[(usize, usize)]) -> DistanceMap {
+        DistanceMap::new(&self.map, sources)
+    }
+}
+
+/// Cost of reaching every tile from the closest of the source tiles.
+/// Moving downhill approaches the sources, moving uphill flees from them
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceMap {
+    distances: Grid<Option<f64>>,
+    pathfinder: Pathfinder,
+}
+
+impl DistanceMap {
+    /// Unit cost for every walkable tile with 4-connectivity
+    pub fn new(map: &Grid<TileType>, sources: &[(usize, usize)]) -> Self {
+        Self::with_pathfinder(map, sources, Pathfinder::new())
+    }
+
+    /// Uses costs and adjacency of the pathfinder, costs should be positive
+    pub fn with_pathfinder(map: &Grid<TileType>, sources: &[(usize, usize)], pathfinder: Pathfinder) -> Self {
+        Self {
+            distances: pathfinder.dijkstra_multi(map, sources),
+            pathfinder,
+        }
+    }
+
+    /// `None` when tile is unreachable or out of bounds
+    pub fn distance(&self, x: usize, y: usize) -> Option<f64> {
+        self.distances.get(x, y).copied().flatten()
+    }
+
+    pub fn distances(&self) -> &Grid<Option<f64>> {
+        &self.distances
+    }
+
+    /// Reachable tile with the largest distance
+    pub fn farthest(&self) -> Option<((usize, usize), f64)> {
+        self.reachable().max_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
+    /// Reachable tiles with their distances in row-major order
+    pub fn reachable(&self) -> impl Iterator<Item = ((usize, usize), f64)> + '_ {
+        self.distances
+            .cells()
+            .filter_map(|(position, distance)| distance.map(|distance| (position, distance)))
+    }
+
+    /// Neighbour closer to the sources, `None` on a source or unreachable tile.
+    /// `map` should be the one this distance map was computed for
+    pub fn downhill_step(&self, map: &Grid<TileType>, position: (usize, usize)) -> Option<(usize, usize)> {
+        let current = self.distance(position.0, position.1)?;
+        self.neighbour_distances(map, position)
+            .filter(|&(_, distance)| distance < current)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(next, _)| next)
+    }
+
+    /// Neighbour farther from the sources, `None` when there is no such neighbour
+    pub fn uphill_step(&self, map: &Grid<TileType>, position: (usize, usize)) -> Option<(usize, usize)> {
+        let current = self.distance(position.0, position.1)?;
+        self.neighbour_distances(map, position)
+            .filter(|&(_, distance)| distance > current)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(next, _)| next)
+    }
+
+    /// Steps downhill from `from` until the closest source, including both ends.
+    /// Empty when `from` is unreachable
+    pub fn downhill_path(&self, map: &Grid<TileType>, from: (usize, usize)) -> Vec<(usize, usize)> {
+        if self.distance(from.0, from.1).is_none() {
+            return Vec::new();
+        }
+
+        let mut path = vec![from];
+        let mut position = from;
+        while let Some(next) = self.downhill_step(map, position) {
+            path.push(next);
+            position = next;
+        }
+        path
+    }
+
+    fn neighbour_distances<'a>(
+        &'a self,
+        map: &'a Grid<TileType>,
+        position: (usize, usize),
+    ) -> impl Iterator<Item = ((usize, usize), f64)> + 'a {
+        self.pathfinder
+            .steps(map, position)
+            .filter_map(|(next, _)| self.distance(next.0, next.1).map(|distance| (next, distance)))
+    }
+}
+
+struct Search {
+    costs: Grid<Option<f64>>,
+    previous: Grid<Option<(usize, usize)>>,
+}
+
+impl Search {
+    fn path(&self, goal: (usize, usize)) -> Option<Path> {
+        let cost = (*self.costs.get(goal.0, goal.1)?)?;
+
+        let mut tiles = vec![goal];
+        let mut position = goal;
+        while let Some(previous) = self.previous[position] {
+            tiles.push(previous);
+            position = previous;
+        }
+        tiles.reverse();
+
+        Some(Path { tiles, cost })
+    }
+}
+
+/// open list entry, ordered so `BinaryHeap` pops the lowest priority first
+struct Node {
+    priority: f64,
+    cost: f64,
+    position: (usize, usize),
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Node {}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority
+            .total_cmp(&self.priority)
+            .then_with(|| self.cost.total_cmp(&other.cost))
+            .then_with(|| self.position.cmp(&other.position))
+    }
+}
@@ -0,0 +1,166 @@
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+use crate::dungeon::{Dungeon, TileType};
+use crate::grid::Grid;
+
+/// Entity placed by `Populator`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpawnPoint<T> {
+    pub position: (usize, usize),
+    /// `None` when dungeon has no rooms, for example caves
+    pub room: Option<usize>,
+    pub payload: T,
+}
+
+/// Payloads with relative weights, payload with weight 2 is picked twice as often as with weight 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpawnTable<T> {
+    entries: Vec<(T, u32)>,
+}
+
+impl<T: Clone> SpawnTable<T> {
+    pub fn new() -> Self {
+        Self { entries: Vec::new() }
+    }
+
+    pub fn entry(mut self, payload: T, weight: u32) -> Self {
+        self.entries.push((payload, weight));
+        self
+    }
+
+    /// `None` when table is empty or all weights are 0
+    pub fn pick(&self, rng: &mut dyn RngCore) -> Option<T> {
+        let total: u64 = self.entries.iter().map(|&(_, weight)| weight as u64).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut roll = rng.gen_range(0..total);
+        for (payload, weight) in &self.entries {
+            if roll < *weight as u64 {
+                return Some(payload.clone());
+            }
+            roll -= *weight as u64;
+        }
+
+        None
+    }
+}
+
+impl<T: Clone> Default for SpawnTable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Places entities on floor tiles of every room after the dungeon is built.
+/// Number of entities in a room is `floor tiles * density` clamped to `min..=max` per room,
+/// dungeon without rooms is populated as one area
+#[derive(Debug, Clone)]
+pub struct Populator<T> {
+    table: SpawnTable<T>,
+    min_per_room: usize,
+    max_per_room: usize,
+    density: f64,
+    skip_entrance_room: bool,
+    start: Option<(usize, usize)>,
+    min_distance_from_start: f64,
+}
+
+impl<T: Clone> Populator<T> {
+    pub fn new(table: SpawnTable<T>) -> Self {
+        Self {
+            table,
+            min_per_room: 0,
+            max_per_room: usize::MAX,
+            density: 0.05,
+            skip_entrance_room: true,
+            start: None,
+            min_distance_from_start: 0.0,
+        }
+    }
+
+    pub fn per_room(mut self, min_per_room: usize, max_per_room: usize) -> Self {
+        self.min_per_room = min_per_room.min(max_per_room);
+        self.max_per_room = min_per_room.max(max_per_room);
+        self
+    }
+
+    /// expected number of entities per floor tile of the room
+    pub fn density(mut self, density: f64) -> Self {
+        self.density = density.max(0.0);
+        self
+    }
+
+    /// room with the entrance stays empty, enabled by default
+    pub fn skip_entrance_room(mut self, skip_entrance_room: bool) -> Self {
+        self.skip_entrance_room = skip_entrance_room;
+        self
+    }
+
+    /// player start, dungeon entrance is used by default
+    pub fn start(mut self, start: (usize, usize)) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    /// minimum walking distance from the player start, tiles which can't be reached from start are skipped
+    pub fn min_distance_from_start(mut self, min_distance_from_start: f64) -> Self {
+        self.min_distance_from_start = min_distance_from_start;
+        self
+    }
+
+    /// Spawn points grouped by room in room order, every tile gets at most one entity
+    pub fn populate(&self, dungeon: &Dungeon, rng: &mut dyn RngCore) -> Vec<SpawnPoint<T>> {
+        let start = self.start.or(dungeon.entrance);
+        let distances = start.map(|start| dungeon.distance_map(&[start]));
+        let is_far_enough = |(x, y): (usize, usize)| match &distances {
+            Some(distances) => distances.distance(x, y).is_some_and(|distance| distance >= self.min_distance_from_start),
+            None => true,
+        };
+
+        let areas = if dungeon.rooms.is_empty() {
+            vec![(None, dungeon.map.positions().collect::<Vec<_>>())]
+        } else {
+            dungeon.rooms
+                .iter()
+                .filter(|room| {
+                    let has_entrance = dungeon.entrance.is_some_and(|(x, y)| room.contains(x, y));
+                    !(self.skip_entrance_room && has_entrance)
+                })
+                .map(|room| {
+                    let tiles = (room.y..(room.y + room.height))
+                        .flat_map(|y| (room.x..(room.x + room.width)).map(move |x| (x, y)))
+                        .collect();
+                    (Some(room.id), tiles)
+                })
+                .collect()
+        };
+
+        let mut occupied = Grid::new(dungeon.map.width(), dungeon.map.height(), false);
+        let mut spawns = Vec::new();
+        for (room, tiles) in areas {
+            let floor_count = tiles.iter().filter(|&&position| dungeon.map[position] == TileType::Floor).count();
+            let mut candidates: Vec<(usize, usize)> = tiles
+                .into_iter()
+                .filter(|&position| dungeon.map[position] == TileType::Floor && !occupied[position])
+                .filter(|&position| is_far_enough(position))
+                .collect();
+
+            let count = ((floor_count as f64 * self.density).round() as usize)
+                .clamp(self.min_per_room, self.max_per_room)
+                .min(candidates.len());
+
+            let (chosen, _) = candidates.partial_shuffle(rng, count);
+            for &mut position in chosen {
+                if let Some(payload) = self.table.pick(rng) {
+                    occupied[position] = true;
+                    spawns.push(SpawnPoint { position, room, payload });
+                }
+            }
+        }
+
+        spawns
+    }
+}
@@ -0,0 +1,189 @@
+use image::{Rgba, RgbaImage};
+use crate::dungeon::{Dungeon, TileType};
+
+/// 3x5 bitmap digits, every row uses three lowest bits, highest bit is the left pixel
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+/// Color of every tile type
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TileColors {
+    pub wall: Rgba<u8>,
+    pub floor: Rgba<u8>,
+    pub door: Rgba<u8>,
+    pub stairs_up: Rgba<u8>,
+    pub stairs_down: Rgba<u8>,
+    pub locked_door: Rgba<u8>,
+}
+
+impl TileColors {
+    pub fn color(&self, tile: TileType) -> Rgba<u8> {
+        match tile {
+            TileType::Wall => self.wall,
+            TileType::Floor => self.floor,
+            TileType::Door => self.door,
+            TileType::StairsUp => self.stairs_up,
+            TileType::StairsDown => self.stairs_down,
+            TileType::LockedDoor => self.locked_door,
+        }
+    }
+}
+
+impl Default for TileColors {
+    fn default() -> Self {
+        Self {
+            wall: Rgba([40, 40, 48, 255]),
+            floor: Rgba([200, 190, 170, 255]),
+            door: Rgba([140, 90, 40, 255]),
+            stairs_up: Rgba([60, 160, 60, 255]),
+            stairs_down: Rgba([180, 50, 50, 255]),
+            locked_door: Rgba([220, 180, 40, 255]),
+        }
+    }
+}
+
+/// Image rendering options, overlay is not drawn when its color is `None`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ImageOptions {
+    /// every tile is rendered as `tile_size` x `tile_size` pixels block
+    pub tile_size: u32,
+    pub colors: TileColors,
+    pub room_outlines: Option<Rgba<u8>>,
+    pub room_ids: Option<Rgba<u8>>,
+    pub corridor_paths: Option<Rgba<u8>>,
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self {
+            tile_size: 8,
+            colors: TileColors::default(),
+            room_outlines: None,
+            room_ids: None,
+            corridor_paths: None,
+        }
+    }
+}
+
+impl ImageOptions {
+    /// Default options with all overlays enabled, useful for debugging generators
+    pub fn with_overlays() -> Self {
+        Self {
+            room_outlines: Some(Rgba([60, 120, 220, 255])),
+            room_ids: Some(Rgba([20, 20, 20, 255])),
+            corridor_paths: Some(Rgba([230, 160, 30, 255])),
+            ..Default::default()
+        }
+    }
+}
+
+impl Dungeon {
+    /// Renders dungeon into image, save it with `image.save("dungeon.png")`
+    pub fn to_image(&self, options: &ImageOptions) -> RgbaImage {
+        let tile_size = options.tile_size.max(1);
+        let mut image = RgbaImage::new(
+            self.map.width() as u32 * tile_size,
+            self.map.height() as u32 * tile_size,
+        );
+
+        for ((x, y), &tile) in self.map.cells() {
+            fill_rect(
+                &mut image,
+                x as u32 * tile_size,
+                y as u32 * tile_size,
+                tile_size,
+                tile_size,
+                options.colors.color(tile),
+            );
+        }
+
+        if let Some(color) = options.corridor_paths {
+            let thickness = (tile_size / 4).max(1);
+            let center = |(x, y): (usize, usize)| {
+                (x as u32 * tile_size + (tile_size - thickness) / 2, y as u32 * tile_size + (tile_size - thickness) / 2)
+            };
+
+            for corridor in &self.corridors {
+                for step in corridor.path.windows(2) {
+                    let (ax, ay) = center(step[0]);
+                    let (bx, by) = center(step[1]);
+                    let (left, top) = (ax.min(bx), ay.min(by));
+                    fill_rect(
+                        &mut image,
+                        left,
+                        top,
+                        ax.max(bx) - left + thickness,
+                        ay.max(by) - top + thickness,
+                        color,
+                    );
+                }
+            }
+        }
+
+        if let Some(color) = options.room_outlines {
+            for room in &self.rooms {
+                let (left, top) = (room.x as u32 * tile_size, room.y as u32 * tile_size);
+                let (width, height) = (room.width as u32 * tile_size, room.height as u32 * tile_size);
+                fill_rect(&mut image, left, top, width, 1, color);
+                fill_rect(&mut image, left, top + height - 1, width, 1, color);
+                fill_rect(&mut image, left, top, 1, height, color);
+                fill_rect(&mut image, left + width - 1, top, 1, height, color);
+            }
+        }
+
+        if let Some(color) = options.room_ids {
+            let scale = (tile_size / 4).max(1);
+            for room in &self.rooms {
+                let left = room.x as u32 * tile_size + 2 * scale;
+                let top = room.y as u32 * tile_size + 2 * scale;
+                draw_number(&mut image, left, top, scale, room.id, color);
+            }
+        }
+
+        image
+    }
+}
+
+/// Fills rectangle, pixels outside of the image are skipped
+fn fill_rect(image: &mut RgbaImage, left: u32, top: u32, width: u32, height: u32, color: Rgba<u8>) {
+    let right = left.saturating_add(width).min(image.width());
+    let bottom = top.saturating_add(height).min(image.height());
+
+    for y in top..bottom {
+        for x in left..right {
+            image.put_pixel(x, y, color);
+        }
+    }
+}
+
+fn draw_number(image: &mut RgbaImage, left: u32, top: u32, scale: u32, number: usize, color: Rgba<u8>) {
+    for (i, digit) in number.to_string().bytes().enumerate() {
+        let glyph = DIGITS[(digit - b'0') as usize];
+        let glyph_left = left + i as u32 * 4 * scale;
+
+        for (row, bits) in glyph.iter().enumerate() {
+            for column in 0..3 {
+                if bits & (0b100 >> column) != 0 {
+                    fill_rect(
+                        image,
+                        glyph_left + column * scale,
+                        top + row as u32 * scale,
+                        scale,
+                        scale,
+                        color,
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+use crate::binary_partition_builder::BinaryPartitionBuilder;
+use crate::cellular_automata_builder::CellularAutomataBuilder;
+use crate::drunkard_walk_builder::DrunkardWalkBuilder;
+use crate::dungeon::{DungeonBuildError, DungeonBuilder};
+use crate::room_placement_builder::RoomPlacementBuilder;
+
+type BuilderFactory = Box<dyn Fn() -> Box<dyn DungeonBuilder>>;
+
+/// Maps names to build algorithms, so algorithm can be selected from config file or command line.
+/// `new` and `Default` register all algorithms of the crate:
+/// `bsp`, `room_placement`, `cellular_automata` and `drunkard_walk`
+pub struct BuilderRegistry {
+    factories: BTreeMap<String, BuilderFactory>,
+}
+
+impl BuilderRegistry {
+    /// Registry with all algorithms of the crate
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry.register("bsp", || Box::new(BinaryPartitionBuilder::new()));
+        registry.register("room_placement", || Box::new(RoomPlacementBuilder::new()));
+        registry.register("cellular_automata", || Box::new(CellularAutomataBuilder::new()));
+        registry.register("drunkard_walk", || Box::new(DrunkardWalkBuilder::new()));
+        registry
+    }
+
+    /// Registry without any algorithm
+    pub fn empty() -> Self {
+        Self { factories: BTreeMap::new() }
+    }
+
+    /// Adds algorithm, algorithm registered before with the same name is replaced
+    pub fn register<Factory>(&mut self, name: impl Into<String>, factory: Factory)
+    where
+        Factory: Fn() -> Box<dyn DungeonBuilder> + 'static,
+    {
+        self.factories.insert(name.into(), Box::new(factory));
+    }
+
+    pub fn create(&self, name: &str) -> Result<Box<dyn DungeonBuilder>, DungeonBuildError> {
+        self.factories
+            .get(name)
+            .map(|factory| factory())
+            .ok_or_else(|| DungeonBuildError::UnknownBuildAlgorithm(name.to_string()))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    /// Registered names in alphabetical order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+}
+
+impl Default for BuilderRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
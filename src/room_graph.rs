@@ -0,0 +1,244 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use crate::corridor::corridor_segments;
+use crate::dungeon::Dungeon;
+
+/// Corridor between two different rooms, `length` is the number of corridor tiles outside of rooms.
+/// Corridor passing through other rooms gives one edge for every pair of consecutive rooms
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoomEdge {
+    pub from: usize,
+    pub to: usize,
+    pub length: usize,
+    /// index in `Dungeon::corridors`
+    pub corridor: usize,
+}
+
+impl RoomEdge {
+    /// room on the other end of the edge
+    pub fn other(&self, room: usize) -> usize {
+        if self.from == room { self.to } else { self.from }
+    }
+}
+
+/// Rooms joined by corridors with their lengths
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoomPath {
+    pub rooms: Vec<usize>,
+    pub length: usize,
+}
+
+/// Rooms as nodes and corridors as edges, parallel corridors are separate edges
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoomGraph {
+    edges: Vec<RoomEdge>,
+    /// edge indices of every room
+    adjacency: Vec<Vec<usize>>,
+}
+
+impl RoomGraph {
+    /// Corridors connecting a room only with itself or with unknown room are skipped
+    pub fn new(dungeon: &Dungeon) -> Self {
+        let mut edges = Vec::new();
+        let mut adjacency = vec![Vec::new(); dungeon.rooms.len()];
+
+        for segment in corridor_segments(dungeon) {
+            adjacency[segment.from].push(edges.len());
+            adjacency[segment.to].push(edges.len());
+            edges.push(RoomEdge {
+                from: segment.from,
+                to: segment.to,
+                length: segment.length,
+                corridor: segment.corridor,
+            });
+        }
+
+        Self { edges, adjacency }
+    }
+
+    pub fn room_count(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    pub fn edges(&self) -> &[RoomEdge] {
+        &self.edges
+    }
+
+    /// Edges touching the room
+    pub fn room_edges(&self, room: usize) -> impl Iterator<Item = &RoomEdge> + '_ {
+        self.adjacency[room].iter().map(|&edge| &self.edges[edge])
+    }
+
+    /// Rooms joined with the room, once per corridor
+    pub fn neighbours(&self, room: usize) -> impl Iterator<Item = usize> + '_ {
+        self.room_edges(room).map(move |edge| edge.other(room))
+    }
+
+    /// Number of corridors touching the room
+    pub fn degree(&self, room: usize) -> usize {
+        self.adjacency[room].len()
+    }
+
+    /// Room with one corridor
+    pub fn is_dead_end(&self, room: usize) -> bool {
+        self.degree(room) == 1
+    }
+
+    /// Number of corridors on the way from `start` to every room, `None` for unreachable rooms
+    pub fn depths(&self, start: usize) -> Vec<Option<usize>> {
+        let mut depths = vec![None; self.room_count()];
+        let mut queue = VecDeque::from([start]);
+        depths[start] = Some(0);
+
+        while let Some(room) = queue.pop_front() {
+            let depth = depths[room].map(|depth| depth + 1);
+            for next in self.neighbours(room) {
+                if depths[next].is_none() {
+                    depths[next] = depth;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        depths
+    }
+
+    /// Path with the fewest corridor tiles outside of rooms, `None` when rooms are not connected
+    pub fn shortest_path(&self, from: usize, to: usize) -> Option<RoomPath> {
+        if from >= self.room_count() || to >= self.room_count() {
+            return None;
+        }
+
+        let mut lengths = vec![None; self.room_count()];
+        let mut previous = vec![None; self.room_count()];
+        let mut open = BinaryHeap::from([Reverse((0, from))]);
+        lengths[from] = Some(0);
+
+        while let Some(Reverse((length, room))) = open.pop() {
+            if lengths[room].is_some_and(|best| length > best) {
+                continue;
+            }
+
+            if room == to {
+                break;
+            }
+
+            for edge in self.room_edges(room) {
+                let next = edge.other(room);
+                let next_length = length + edge.length;
+                if lengths[next].is_none_or(|best| next_length < best) {
+                    lengths[next] = Some(next_length);
+                    previous[next] = Some(room);
+                    open.push(Reverse((next_length, next)));
+                }
+            }
+        }
+
+        let length = lengths[to]?;
+        let mut rooms = vec![to];
+        let mut room = to;
+        while let Some(previous) = previous[room] {
+            rooms.push(previous);
+            room = previous;
+        }
+        rooms.reverse();
+
+        Some(RoomPath { rooms, length })
+    }
+
+    /// Rooms which split the graph into more parts when removed, in ascending order
+    pub fn articulation_points(&self) -> Vec<usize> {
+        let lowlinks = self.lowlinks();
+        (0..self.room_count()).filter(|&room| lowlinks.is_articulation[room]).collect()
+    }
+
+    /// Edge indices of corridors which split the graph into more parts when removed
+    pub fn bridges(&self) -> Vec<usize> {
+        self.lowlinks().bridges
+    }
+
+    /// At least one room can be reached by two different routes
+    pub fn has_cycle(&self) -> bool {
+        self.bridges().len() < self.edges.len()
+    }
+
+    /// Edge indices of corridors which lie on a cycle, removing one of them keeps rooms connected
+    pub fn cycle_edges(&self) -> Vec<usize> {
+        let bridges = self.bridges();
+        (0..self.edges.len()).filter(|edge| !bridges.contains(edge)).collect()
+    }
+
+    /// Tarjan's depth first search over every connected part of the graph
+    fn lowlinks(&self) -> Lowlinks {
+        let mut lowlinks = Lowlinks {
+            order: vec![None; self.room_count()],
+            low: vec![0; self.room_count()],
+            is_articulation: vec![false; self.room_count()],
+            bridges: Vec::new(),
+            counter: 0,
+        };
+
+        for room in 0..self.room_count() {
+            if lowlinks.order[room].is_none() {
+                lowlinks.visit(self, room, None);
+            }
+        }
+
+        lowlinks.bridges.sort_unstable();
+        lowlinks
+    }
+}
+
+impl Dungeon {
+    pub fn room_graph(&self) -> RoomGraph {
+        RoomGraph::new(self)
+    }
+}
+
+struct Lowlinks {
+    order: Vec<Option<usize>>,
+    low: Vec<usize>,
+    is_articulation: Vec<bool>,
+    bridges: Vec<usize>,
+    counter: usize,
+}
+
+impl Lowlinks {
+    /// `parent_edge` is skipped by index, so parallel corridors form a cycle
+    fn visit(&mut self, graph: &RoomGraph, room: usize, parent_edge: Option<usize>) {
+        let order = self.counter;
+        self.counter += 1;
+        self.order[room] = Some(order);
+        self.low[room] = order;
+        let mut children = 0;
+
+        for &edge in &graph.adjacency[room] {
+            if Some(edge) == parent_edge {
+                continue;
+            }
+
+            let next = graph.edges[edge].other(room);
+            match self.order[next] {
+                Some(next_order) => self.low[room] = self.low[room].min(next_order),
+                None => {
+                    children += 1;
+                    self.visit(graph, next, Some(edge));
+                    self.low[room] = self.low[room].min(self.low[next]);
+
+                    if self.low[next] > order {
+                        self.bridges.push(edge);
+                    }
+                    if parent_edge.is_some() && self.low[next] >= order {
+                        self.is_articulation[room] = true;
+                    }
+                }
+            }
+        }
+
+        if parent_edge.is_none() && children > 1 {
+            self.is_articulation[room] = true;
+        }
+    }
+}
@@ -1,26 +1,86 @@
-use std::cmp::{min, max};
-use rand::Rng;
+use rand::{Rng, RngCore};
+use crate::corridor::{carve_path, l_shaped_path};
+use crate::doors::place_doors;
 use crate::dungeon::{
-    Dungeon, DungeonBuilder, DungeonBuildConfig, DungeonBuildError,
-    TileType
+    CorridorInfo, Dungeon, DungeonBuilder, DungeonBuildConfig, DungeonBuildError,
+    RoomInfo, TileType
 };
+use crate::grid::Grid;
 
-pub struct RoomPlacementBuilder;
+/// Places random rooms which don't overlap and connects them with minimum spanning tree of corridors,
+/// extra corridors between close rooms add loops to the tree
+#[derive(Debug, Copy, Clone)]
+pub struct RoomPlacementBuilder {
+    max_attempts: Option<usize>,
+    target_room_count: Option<usize>,
+    min_spacing: usize,
+    extra_corridor_ratio: f64,
+}
+
+impl RoomPlacementBuilder {
+    pub fn new() -> Self {
+        Self {
+            max_attempts: None,
+            target_room_count: None,
+            min_spacing: 0,
+            extra_corridor_ratio: 0.1,
+        }
+    }
+
+    /// number of random rooms tried, by default `(width * height) / (min_room_size * max_room_size)`
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// placement stops when dungeon has this many rooms
+    pub fn target_room_count(mut self, target_room_count: usize) -> Self {
+        self.target_room_count = Some(target_room_count);
+        self
+    }
+
+    /// minimum number of wall tiles between rooms, with 0 rooms can touch each other
+    pub fn min_spacing(mut self, min_spacing: usize) -> Self {
+        self.min_spacing = min_spacing;
+        self
+    }
+
+    /// extra corridors as fraction of spanning tree corridors, rounded up.
+    /// 0 gives tree-like layout, 1 doubles the number of corridors
+    pub fn extra_corridor_ratio(mut self, extra_corridor_ratio: f64) -> Self {
+        self.extra_corridor_ratio = extra_corridor_ratio.max(0.0);
+        self
+    }
+}
+
+impl Default for RoomPlacementBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl DungeonBuilder for RoomPlacementBuilder {
-    fn build(self, build_config: DungeonBuildConfig) -> Result<Dungeon, DungeonBuildError> {
+    fn build(&self, build_config: DungeonBuildConfig, rng: &mut dyn RngCore) -> Result<Dungeon, DungeonBuildError> {
         let width = build_config.dungeon_size.width;
         let height = build_config.dungeon_size.height;
         let room_min_size = build_config.room_size.min_room_size;
         let room_max_size = build_config.room_size.max_room_size;
 
-        let mut map = vec![vec![TileType::Wall; width]; height];
-        let mut rng = rand::thread_rng();
+        if width < 3 || height < 3 {
+            return Err(DungeonBuildError::NoRoomsCreated);
+        }
+
+        let mut map = Grid::new(width, height, TileType::Wall);
         let mut rooms = Vec::new();
 
-        let max_rooms = (width * height) / (room_min_size * room_max_size);
+        let max_attempts = self.max_attempts
+            .unwrap_or((width * height) / (room_min_size * room_max_size));
+
+        for _ in 0..max_attempts {
+            if self.target_room_count.is_some_and(|target| rooms.len() >= target) {
+                break;
+            }
 
-        for _ in 0..max_rooms {
             let next_room_w = rng.gen_range(room_min_size..=room_max_size);
             let next_room_h = rng.gen_range(room_min_size..=room_max_size);
 
@@ -33,10 +93,10 @@ impl DungeonBuilder for RoomPlacementBuilder {
 
             let next_room = Room::new(x, y, next_room_w, next_room_h);
 
-            if rooms.iter().all(|r| !next_room.intersects(r)) {
-                for i in next_room.x..(next_room.x + next_room.width) {
-                    for j in next_room.y..(next_room.y + next_room.height) {
-                        map[j][i] = TileType::Floor;
+            if rooms.iter().all(|r| !next_room.intersects(r, self.min_spacing)) {
+                for y in next_room.y..(next_room.y + next_room.height) {
+                    for x in next_room.x..(next_room.x + next_room.width) {
+                        map[(x, y)] = TileType::Floor;
                     }
                 }
                 rooms.push(next_room);
@@ -69,8 +129,8 @@ impl DungeonBuilder for RoomPlacementBuilder {
             if union_find.find(*i) != union_find.find(*j) {
                 union_find.union(*i, *j);
 
-                create_corridor(&mut map, &rooms[*i], &rooms[*j]);
-                corridors.push((*i, *j));
+                let path = create_corridor(&mut map, rng, &rooms[*i], &rooms[*j]);
+                corridors.push(CorridorInfo::new(*i, *j, path));
 
                 if union_find.count() == 1 {
                     break;
@@ -78,24 +138,41 @@ impl DungeonBuilder for RoomPlacementBuilder {
             }
         }
 
-        let extra_corridors = rng.gen_range(0..2);
+        // shortest edges which are not in the tree make small loops
+        let extra_corridors = (corridors.len() as f64 * self.extra_corridor_ratio).ceil() as usize;
         let mut added = 0;
         for ((i, j), _) in &edges {
-            if !corridors.contains(&(*i, *j)) && !corridors.contains(&(*j, *i)) {
-                create_corridor(&mut map, &rooms[*i], &rooms[*j]);
+            if added >= extra_corridors {
+                break;
+            }
+
+            let is_connected = corridors.iter()
+                .any(|c| (c.from_room, c.to_room) == (*i, *j) || (c.from_room, c.to_room) == (*j, *i));
+
+            if !is_connected {
+                let path = create_corridor(&mut map, rng, &rooms[*i], &rooms[*j]);
+                corridors.push(CorridorInfo::new(*i, *j, path));
                 added += 1;
-                if added >= extra_corridors {
-                    break;
-                }
             }
         }
 
-        if build_config.should_place_doors {
-            place_doors(&mut map);
+        let rooms: Vec<RoomInfo> = rooms
+            .iter()
+            .enumerate()
+            .map(|(id, room)| RoomInfo::new(id, room.x, room.y, room.width, room.height))
+            .collect();
 
+        if build_config.should_place_doors {
+            place_doors(&mut map, &rooms);
         }
 
-        Ok(Dungeon { map })
+        Ok(Dungeon {
+            map,
+            rooms,
+            corridors,
+            entrance: None,
+            exit: None,
+        })
     }
 }
 
@@ -123,11 +200,12 @@ impl Room {
         }
     }
 
-    pub fn intersects(&self, other: &Room) -> bool {
-        self.x < other.x + other.width
-            && self.x + self.width > other.x
-            && self.y < other.y + other.height
-            && self.y + self.height > other.y
+    /// rooms closer than `spacing` tiles are treated as intersecting
+    pub fn intersects(&self, other: &Room, spacing: usize) -> bool {
+        self.x < other.x + other.width + spacing
+            && self.x + self.width + spacing > other.x
+            && self.y < other.y + other.height + spacing
+            && self.y + self.height + spacing > other.y
     }
 }
 
@@ -180,53 +258,17 @@ impl UnionFind {
     }
 }
 
-fn create_corridor(map: &mut [Vec<TileType>], room1: &Room, room2: &Room) {
-    let mut rng = rand::thread_rng();
-
-    let (x1, y1) = (room1.center_x, room1.center_y);
-    let (x2, y2) = (room2.center_x, room2.center_y);
-
-    if rng.gen_bool(0.5) {
-        for x in min(x1, x2)..=max(x1, x2) {
-            map[y1][x] = TileType::Floor;
-        }
-        for y in min(y1, y2)..=max(y1, y2) {
-            map[y][x2] = TileType::Floor;
-        }
-    } else {
-        for y in min(y1, y2)..=max(y1, y2) {
-            map[y][x1] = TileType::Floor;
-        }
-        for x in min(x1, x2)..=max(x1, x2) {
-            map[y2][x] = TileType::Floor;
-        }
-    }
-}
-
-/// algo to place doors outside of rooms
-/// this algo doesn't work correctly, but I didn't have time to fix it
-fn place_doors(map: &mut [Vec<TileType>]) {
-    let height = map.len();
-    let width = map[0].len();
-
-    for y in 1..(height - 1) {
-        for x in 1..(width - 1) {
-            if map[y][x] == TileType::Wall {
-                let adjacent_floors = [
-                    map[y - 1][x],
-                    map[y + 1][x],
-                    map[y][x - 1],
-                    map[y][x + 1],
-                ];
-                let floor_count = adjacent_floors
-                    .iter()
-                    .filter(|&&tile| tile == TileType::Floor)
-                    .count();
-
-                if floor_count >= 2 {
-                    map[y][x] = TileType::Door;
-                }
-            }
-        }
-    }
+fn create_corridor(
+    map: &mut Grid<TileType>,
+    rng: &mut dyn RngCore,
+    room1: &Room,
+    room2: &Room,
+) -> Vec<(usize, usize)> {
+    let path = l_shaped_path(
+        (room1.center_x, room1.center_y),
+        (room2.center_x, room2.center_y),
+        rng.gen_bool(0.5),
+    );
+    carve_path(map, &path);
+    path
 }
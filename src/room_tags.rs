@@ -0,0 +1,159 @@
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+use crate::dungeon::{Dungeon, RoomInfo};
+use crate::room_graph::RoomGraph;
+
+/// Purpose of the room assigned by `RoomTagger`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RoomTag {
+    /// room with the entrance
+    Start,
+    Boss,
+    Treasure,
+    Shop,
+    /// no rule matched the room
+    Empty,
+}
+
+/// Conditions for assigning a tag, room matches when all of them hold.
+/// Depth is the number of corridors between the room and the start room
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TagRule {
+    tag: RoomTag,
+    weight: u32,
+    min_area: usize,
+    max_area: usize,
+    depth: Option<(usize, usize)>,
+    dead_end: Option<bool>,
+    max_count: Option<usize>,
+}
+
+impl TagRule {
+    /// rule matching every room, `weight` is relative to other matching rules
+    pub fn new(tag: RoomTag, weight: u32) -> Self {
+        Self {
+            tag,
+            weight,
+            min_area: 0,
+            max_area: usize::MAX,
+            depth: None,
+            dead_end: None,
+            max_count: None,
+        }
+    }
+
+    /// room `width * height` in `min_area..=max_area`
+    pub fn area(mut self, min_area: usize, max_area: usize) -> Self {
+        self.min_area = min_area;
+        self.max_area = max_area;
+        self
+    }
+
+    /// depth in `min_depth..=max_depth`, rooms not reachable from start have no depth and never match
+    pub fn depth(mut self, min_depth: usize, max_depth: usize) -> Self {
+        self.depth = Some((min_depth, max_depth));
+        self
+    }
+
+    /// `true` matches only rooms with one corridor, `false` only rooms with more
+    pub fn dead_end(mut self, dead_end: bool) -> Self {
+        self.dead_end = Some(dead_end);
+        self
+    }
+
+    /// at most `max_count` rooms get the tag from this rule
+    pub fn max_count(mut self, max_count: usize) -> Self {
+        self.max_count = Some(max_count);
+        self
+    }
+
+    fn matches(&self, room: &RoomInfo, depth: Option<usize>, is_dead_end: bool) -> bool {
+        let area = room.width * room.height;
+        let depth_matches = match self.depth {
+            Some((min_depth, max_depth)) => depth.is_some_and(|depth| (min_depth..=max_depth).contains(&depth)),
+            None => true,
+        };
+
+        (self.min_area..=self.max_area).contains(&area)
+            && depth_matches
+            && self.dead_end.is_none_or(|dead_end| dead_end == is_dead_end)
+    }
+}
+
+/// Assigns `RoomTag` to every room of the dungeon. Room with the entrance, or the first room
+/// when stairs are not placed, is tagged `Start`. Other rooms are visited from the deepest one
+/// and get a tag of one of the matching rules picked by weight, so limited tags go to deep rooms first
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoomTagger {
+    rules: Vec<TagRule>,
+}
+
+impl RoomTagger {
+    /// without rules every room except the start one is tagged `Empty`
+    pub fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    pub fn rule(mut self, rule: TagRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Overwrites tags of all rooms
+    pub fn tag(&self, dungeon: &mut Dungeon, rng: &mut dyn RngCore) {
+        if dungeon.rooms.is_empty() {
+            return;
+        }
+
+        let start = dungeon.entrance
+            .and_then(|(x, y)| dungeon.rooms.iter().position(|room| room.contains(x, y)))
+            .unwrap_or(0);
+        let graph = RoomGraph::new(dungeon);
+        let depths = graph.depths(start);
+
+        let mut order: Vec<usize> = (0..dungeon.rooms.len()).filter(|&id| id != start).collect();
+        order.shuffle(rng);
+        order.sort_by_key(|&id| std::cmp::Reverse(depths[id]));
+
+        let mut remaining: Vec<Option<usize>> = self.rules.iter().map(|rule| rule.max_count).collect();
+        dungeon.rooms[start].tag = Some(RoomTag::Start);
+
+        for id in order {
+            let is_dead_end = graph.is_dead_end(id);
+            let candidates: Vec<usize> = (0..self.rules.len())
+                .filter(|&i| self.rules[i].weight > 0 && remaining[i] != Some(0))
+                .filter(|&i| self.rules[i].matches(&dungeon.rooms[id], depths[id], is_dead_end))
+                .collect();
+
+            let total: u64 = candidates.iter().map(|&i| self.rules[i].weight as u64).sum();
+            let mut tag = RoomTag::Empty;
+            if total > 0 {
+                let mut roll = rng.gen_range(0..total);
+                for i in candidates {
+                    let weight = self.rules[i].weight as u64;
+                    if roll < weight {
+                        tag = self.rules[i].tag;
+                        remaining[i] = remaining[i].map(|count| count - 1);
+                        break;
+                    }
+                    roll -= weight;
+                }
+            }
+
+            dungeon.rooms[id].tag = Some(tag);
+        }
+    }
+}
+
+impl Dungeon {
+    pub fn tag_rooms(&mut self, tagger: &RoomTagger, rng: &mut dyn RngCore) {
+        tagger.tag(self, rng);
+    }
+
+    pub fn rooms_with_tag(&self, tag: RoomTag) -> impl Iterator<Item = &RoomInfo> + '_ {
+        self.rooms.iter().filter(move |room| room.tag == Some(tag))
+    }
+}
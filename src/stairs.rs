@@ -0,0 +1,87 @@
+use crate::dungeon::{Dungeon, DungeonBuildError, TileType};
+use crate::grid::Grid;
+use crate::pathfinding::DistanceMap;
+
+/// Places entrance and exit as far from each other as possible by path distance.
+/// When dungeon has rooms, stairs are placed in centers of two different rooms,
+/// otherwise on the two most distant walkable tiles
+pub(crate) fn place_stairs(dungeon: &mut Dungeon) -> Result<(), DungeonBuildError> {
+    let (entrance, exit) = if dungeon.rooms.len() >= 2 {
+        farthest_rooms(dungeon)
+    } else {
+        farthest_tiles(&dungeon.map)
+    }
+    .ok_or(DungeonBuildError::NoStairsPlace)?;
+
+    dungeon.map[entrance] = TileType::StairsUp;
+    dungeon.map[exit] = TileType::StairsDown;
+    dungeon.entrance = Some(entrance);
+    dungeon.exit = Some(exit);
+
+    Ok(())
+}
+
+/// Places entrance on the floor tile closest to `preferred` position,
+/// so it can be aligned with the exit of the level above, exit is placed
+/// in the room most distant from the entrance. When dungeon has rooms,
+/// entrance is placed inside of a room, never in a corridor or on a door
+pub(crate) fn place_stairs_near(dungeon: &mut Dungeon, preferred: (usize, usize)) -> Result<(), DungeonBuildError> {
+    let entrance = dungeon.map
+        .positions()
+        .filter(|&position| dungeon.map[position] == TileType::Floor)
+        .filter(|&(x, y)| dungeon.rooms.is_empty() || dungeon.rooms.iter().any(|room| room.contains(x, y)))
+        .min_by_key(|&(x, y)| x.abs_diff(preferred.0) + y.abs_diff(preferred.1))
+        .ok_or(DungeonBuildError::NoStairsPlace)?;
+
+    let distances = DistanceMap::new(&dungeon.map, &[entrance]);
+    let exit = if dungeon.rooms.len() >= 2 {
+        dungeon.rooms
+            .iter()
+            .filter(|room| !room.contains(entrance.0, entrance.1))
+            .filter_map(|room| distances.distance(room.center.0, room.center.1).map(|distance| (room.center, distance)))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+    } else {
+        distances.farthest()
+    };
+
+    let exit = match exit {
+        Some((exit, distance)) if distance > 0.0 => exit,
+        _ => return Err(DungeonBuildError::NoStairsPlace),
+    };
+
+    dungeon.map[entrance] = TileType::StairsUp;
+    dungeon.map[exit] = TileType::StairsDown;
+    dungeon.entrance = Some(entrance);
+    dungeon.exit = Some(exit);
+
+    Ok(())
+}
+
+fn farthest_rooms(dungeon: &Dungeon) -> Option<((usize, usize), (usize, usize))> {
+    let mut best = None;
+    let mut best_distance = 0.0;
+
+    for (i, room) in dungeon.rooms.iter().enumerate() {
+        let distances = DistanceMap::new(&dungeon.map, &[room.center]);
+        for other in &dungeon.rooms[(i + 1)..] {
+            if let Some(distance) = distances.distance(other.center.0, other.center.1) {
+                if distance > best_distance {
+                    best_distance = distance;
+                    best = Some((room.center, other.center));
+                }
+            }
+        }
+    }
+
+    best
+}
+
+/// two passes of search, the farthest tile from any tile is one end
+/// of the longest path and the farthest tile from it is another end
+fn farthest_tiles(map: &Grid<TileType>) -> Option<((usize, usize), (usize, usize))> {
+    let start = map.positions().find(|&position| map[position].is_walkable())?;
+    let (first, _) = DistanceMap::new(map, &[start]).farthest()?;
+    let (second, distance) = DistanceMap::new(map, &[first]).farthest()?;
+
+    (distance > 0.0).then_some((first, second))
+}
@@ -0,0 +1,341 @@
+use serde_json::{json, Value};
+use crate::dungeon::{Dungeon, TileType};
+
+const TILED_VERSION: &str = "1.10";
+
+/// Tileset GIDs used for every tile type, GID 0 means empty tile in Tiled
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileGids {
+    pub wall: u32,
+    pub floor: u32,
+    pub door: u32,
+    pub stairs_up: u32,
+    pub stairs_down: u32,
+    pub locked_door: u32,
+}
+
+impl TileGids {
+    pub fn gid(&self, tile: TileType) -> u32 {
+        match tile {
+            TileType::Wall => self.wall,
+            TileType::Floor => self.floor,
+            TileType::Door => self.door,
+            TileType::StairsUp => self.stairs_up,
+            TileType::StairsDown => self.stairs_down,
+            TileType::LockedDoor => self.locked_door,
+        }
+    }
+}
+
+impl Default for TileGids {
+    /// tile ids are the same as `TileType` values, tileset starts from GID 1
+    fn default() -> Self {
+        Self {
+            wall: TileType::Wall as u32 + 1,
+            floor: TileType::Floor as u32 + 1,
+            door: TileType::Door as u32 + 1,
+            stairs_up: TileType::StairsUp as u32 + 1,
+            stairs_down: TileType::StairsDown as u32 + 1,
+            locked_door: TileType::LockedDoor as u32 + 1,
+        }
+    }
+}
+
+/// Writes dungeon as Tiled map with one tile layer and one object layer,
+/// object layer contains rooms as rectangles, corridors as polylines and stairs as points
+#[derive(Debug, Clone)]
+pub struct TiledExporter {
+    tile_gids: TileGids,
+    tile_width: u32,
+    tile_height: u32,
+    tileset_source: String,
+}
+
+enum Shape {
+    Rectangle { width: u32, height: u32 },
+    Polyline(Vec<(i64, i64)>),
+    Point,
+}
+
+struct MapObject {
+    name: String,
+    kind: &'static str,
+    x: u32,
+    y: u32,
+    shape: Shape,
+    properties: Vec<(&'static str, usize)>,
+}
+
+impl TiledExporter {
+    pub fn new() -> Self {
+        Self {
+            tile_gids: TileGids::default(),
+            tile_width: 16,
+            tile_height: 16,
+            tileset_source: String::from("tiles.tsx"),
+        }
+    }
+
+    pub fn tile_gids(mut self, tile_gids: TileGids) -> Self {
+        self.tile_gids = tile_gids;
+        self
+    }
+
+    pub fn tile_size(mut self, tile_width: u32, tile_height: u32) -> Self {
+        self.tile_width = tile_width;
+        self.tile_height = tile_height;
+        self
+    }
+
+    /// path to external tileset, relative to the exported map
+    pub fn tileset_source(mut self, tileset_source: impl Into<String>) -> Self {
+        self.tileset_source = tileset_source.into();
+        self
+    }
+
+    /// Tiled JSON map format
+    pub fn to_tmj(&self, dungeon: &Dungeon) -> String {
+        let objects = self.objects(dungeon);
+        let data: Vec<u32> = dungeon.map.iter().map(|&tile| self.tile_gids.gid(tile)).collect();
+
+        let objects: Vec<Value> = objects
+            .iter()
+            .enumerate()
+            .map(|(i, object)| {
+                let mut value = json!({
+                    "id": i + 1,
+                    "name": object.name,
+                    "type": object.kind,
+                    "x": object.x,
+                    "y": object.y,
+                    "rotation": 0,
+                    "visible": true,
+                    "properties": object.properties
+                        .iter()
+                        .map(|(name, value)| json!({ "name": name, "type": "int", "value": value }))
+                        .collect::<Vec<_>>(),
+                });
+
+                match &object.shape {
+                    Shape::Rectangle { width, height } => {
+                        value["width"] = json!(width);
+                        value["height"] = json!(height);
+                    }
+                    Shape::Polyline(points) => {
+                        value["polyline"] = points
+                            .iter()
+                            .map(|(x, y)| json!({ "x": x, "y": y }))
+                            .collect();
+                    }
+                    Shape::Point => value["point"] = json!(true),
+                }
+
+                value
+            })
+            .collect();
+
+        let map = json!({
+            "type": "map",
+            "version": TILED_VERSION,
+            "orientation": "orthogonal",
+            "renderorder": "right-down",
+            "infinite": false,
+            "width": dungeon.map.width(),
+            "height": dungeon.map.height(),
+            "tilewidth": self.tile_width,
+            "tileheight": self.tile_height,
+            "nextlayerid": 3,
+            "nextobjectid": objects.len() + 1,
+            "tilesets": [{ "firstgid": 1, "source": self.tileset_source }],
+            "layers": [
+                {
+                    "id": 1,
+                    "name": "tiles",
+                    "type": "tilelayer",
+                    "x": 0,
+                    "y": 0,
+                    "width": dungeon.map.width(),
+                    "height": dungeon.map.height(),
+                    "opacity": 1,
+                    "visible": true,
+                    "data": data,
+                },
+                {
+                    "id": 2,
+                    "name": "rooms",
+                    "type": "objectgroup",
+                    "draworder": "topdown",
+                    "x": 0,
+                    "y": 0,
+                    "opacity": 1,
+                    "visible": true,
+                    "objects": objects,
+                },
+            ],
+        });
+
+        map.to_string()
+    }
+
+    /// Tiled XML map format, tile layer is written as csv
+    pub fn to_tmx(&self, dungeon: &Dungeon) -> String {
+        let objects = self.objects(dungeon);
+        let width = dungeon.map.width();
+        let height = dungeon.map.height();
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<map version=\"{TILED_VERSION}\" orientation=\"orthogonal\" renderorder=\"right-down\" \
+             width=\"{width}\" height=\"{height}\" tilewidth=\"{}\" tileheight=\"{}\" infinite=\"0\" \
+             nextlayerid=\"3\" nextobjectid=\"{}\">\n",
+            self.tile_width,
+            self.tile_height,
+            objects.len() + 1,
+        ));
+        xml.push_str(&format!(" <tileset firstgid=\"1\" source=\"{}\"/>\n", escape_xml(&self.tileset_source)));
+
+        xml.push_str(&format!(" <layer id=\"1\" name=\"tiles\" width=\"{width}\" height=\"{height}\">\n"));
+        xml.push_str("  <data encoding=\"csv\">\n");
+        let rows: Vec<String> = dungeon.map
+            .rows()
+            .map(|row| row.iter().map(|&tile| self.tile_gids.gid(tile).to_string()).collect::<Vec<_>>().join(","))
+            .collect();
+        xml.push_str(&rows.join(",\n"));
+        xml.push_str("\n  </data>\n </layer>\n");
+
+        xml.push_str(" <objectgroup id=\"2\" name=\"rooms\">\n");
+        for (i, object) in objects.iter().enumerate() {
+            xml.push_str(&format!(
+                "  <object id=\"{}\" name=\"{}\" type=\"{}\" x=\"{}\" y=\"{}\"",
+                i + 1,
+                escape_xml(&object.name),
+                object.kind,
+                object.x,
+                object.y,
+            ));
+            if let Shape::Rectangle { width, height } = object.shape {
+                xml.push_str(&format!(" width=\"{width}\" height=\"{height}\""));
+            }
+            xml.push_str(">\n");
+
+            if !object.properties.is_empty() {
+                xml.push_str("   <properties>\n");
+                for (name, value) in &object.properties {
+                    xml.push_str(&format!("    <property name=\"{name}\" type=\"int\" value=\"{value}\"/>\n"));
+                }
+                xml.push_str("   </properties>\n");
+            }
+
+            match &object.shape {
+                Shape::Rectangle { .. } => {}
+                Shape::Polyline(points) => {
+                    let points: Vec<String> = points.iter().map(|(x, y)| format!("{x},{y}")).collect();
+                    xml.push_str(&format!("   <polyline points=\"{}\"/>\n", points.join(" ")));
+                }
+                Shape::Point => xml.push_str("   <point/>\n"),
+            }
+
+            xml.push_str("  </object>\n");
+        }
+        xml.push_str(" </objectgroup>\n</map>\n");
+
+        xml
+    }
+
+    fn objects(&self, dungeon: &Dungeon) -> Vec<MapObject> {
+        let mut objects = Vec::new();
+
+        for room in &dungeon.rooms {
+            objects.push(MapObject {
+                name: format!("room {}", room.id),
+                kind: "room",
+                x: room.x as u32 * self.tile_width,
+                y: room.y as u32 * self.tile_height,
+                shape: Shape::Rectangle {
+                    width: room.width as u32 * self.tile_width,
+                    height: room.height as u32 * self.tile_height,
+                },
+                properties: vec![("room_id", room.id)],
+            });
+        }
+
+        for (i, corridor) in dungeon.corridors.iter().enumerate() {
+            let (start_x, start_y) = self.tile_center(corridor.start);
+            let points = corner_points(&corridor.path)
+                .into_iter()
+                .map(|position| {
+                    let (x, y) = self.tile_center(position);
+                    (x as i64 - start_x as i64, y as i64 - start_y as i64)
+                })
+                .collect();
+
+            objects.push(MapObject {
+                name: format!("corridor {i}"),
+                kind: "corridor",
+                x: start_x,
+                y: start_y,
+                shape: Shape::Polyline(points),
+                properties: vec![("from_room", corridor.from_room), ("to_room", corridor.to_room)],
+            });
+        }
+
+        let stairs = [("entrance", dungeon.entrance), ("exit", dungeon.exit)];
+        for (name, position) in stairs {
+            if let Some(position) = position {
+                let (x, y) = self.tile_center(position);
+                objects.push(MapObject {
+                    name: name.to_string(),
+                    kind: "stairs",
+                    x,
+                    y,
+                    shape: Shape::Point,
+                    properties: Vec::new(),
+                });
+            }
+        }
+
+        objects
+    }
+
+    fn tile_center(&self, (x, y): (usize, usize)) -> (u32, u32) {
+        (
+            x as u32 * self.tile_width + self.tile_width / 2,
+            y as u32 * self.tile_height + self.tile_height / 2,
+        )
+    }
+}
+
+impl Default for TiledExporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// start, end and every tile where path turns
+fn corner_points(path: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut points: Vec<(usize, usize)> = path.first().copied().into_iter().collect();
+
+    for step in path.windows(3) {
+        let (a, b, c) = (step[0], step[1], step[2]);
+        let is_straight = (a.0 == b.0 && b.0 == c.0) || (a.1 == b.1 && b.1 == c.1);
+        if !is_straight {
+            points.push(b);
+        }
+    }
+
+    if path.len() > 1 {
+        points.extend(path.last().copied());
+    }
+
+    points
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}